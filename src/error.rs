@@ -0,0 +1,45 @@
+use core::fmt;
+
+/* Error codes mirror the BLADERF_ERR_* values returned by libbladerf */
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum BladerfError {
+    Unexpected,
+    Inval,
+    Io,
+    Timeout,
+    Unsupported,
+    TimePast,
+    QueueFull,
+}
+
+impl BladerfError {
+    pub fn code(&self) -> i32 {
+        match self {
+            BladerfError::Unexpected => -1,
+            BladerfError::Inval => -3,
+            BladerfError::Io => -5,
+            BladerfError::Timeout => -6,
+            BladerfError::Unsupported => -8,
+            BladerfError::TimePast => -14,
+            BladerfError::QueueFull => -15,
+        }
+    }
+}
+
+impl fmt::Display for BladerfError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let msg = match self {
+            BladerfError::Unexpected => "An unexpected error occurred.",
+            BladerfError::Inval => "Invalid operation or parameter.",
+            BladerfError::Io => "File/Device I/O error.",
+            BladerfError::Timeout => "Operation timed out.",
+            BladerfError::Unsupported => "Operation not supported.",
+            BladerfError::TimePast => "Requested timestamp is in the past.",
+            BladerfError::QueueFull => "Queue is full. Try again later.",
+        };
+
+        write!(f, "Error {}: {}", self.code(), msg)
+    }
+}
+
+impl core::error::Error for BladerfError {}
@@ -5,15 +5,30 @@ extern crate alloc;
 #[cfg(feature = "std")]
 extern crate std;
 
-use crate::nios::nios_access::nios_lms6_read;
+use crate::error::BladerfError;
+use crate::nios::nios_access::{nios_rffe_control_read, nios_rffe_control_write};
+#[cfg(feature = "std")]
+use crate::nios::fpga_config::{fpga_config_modify, fpga_config_read, RxMux};
+use crate::nios::rffe_control::RFFE_CONTROL_MIMO_RX_EN_0;
 #[cfg(feature = "nusb")]
 use ::nusb::{DeviceInfo, Interface};
 use usb::*;
 
 pub mod usb;
 pub mod nios;
+pub mod error;
+pub mod trigger;
+pub mod retune;
+#[cfg(feature = "std")]
+pub mod timebase;
+#[cfg(feature = "std")]
+pub mod stream;
+#[cfg(feature = "std")]
+pub mod rfic;
+#[cfg(feature = "std")]
+pub mod lms6;
 
-#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Debug)]
 pub struct BladerfVersion {
     pub major: u8,
     pub minor: u8,
@@ -31,6 +46,18 @@ const BLADE_USB_CMD_RF_TX: u8 = 5;
 // const BLADE_USB_CMD_QUERY_FPGA_SOURCE: u8 = 8;
 // const BLADE_USB_CMD_FLASH_READ: u8 = 100;
 
+/* USB product IDs, both under the Nuand vendor ID 0x2cf0 */
+pub const BLADERF1_PRODUCT_ID: u16 = 0x5246;
+pub const BLADERF2_PRODUCT_ID: u16 = 0x5250;
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum BladerfBoard {
+    /* bladeRF x40/x115, LMS6002D */
+    BladeRF1,
+    /* bladeRF 2.0 micro, AD9361 */
+    BladeRF2,
+}
+
 #[repr(u8)]
 #[derive(Copy, Clone, Eq, PartialEq)]
 pub enum BladerfDirection {
@@ -38,6 +65,63 @@ pub enum BladerfDirection {
     TX,
 }
 
+/* Channel numbering follows libbladerf: BLADERF_CHANNEL_RX(n) = n << 1, BLADERF_CHANNEL_TX(n) = (n << 1) | 1 */
+#[repr(u8)]
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum BladerfChannel {
+    RX1 = 0,
+    TX1 = 1,
+    RX2 = 2,
+    TX2 = 3,
+}
+
+impl BladerfChannel {
+    pub fn direction(&self) -> BladerfDirection {
+        match self {
+            BladerfChannel::RX1 | BladerfChannel::RX2 => BladerfDirection::RX,
+            BladerfChannel::TX1 | BladerfChannel::TX2 => BladerfDirection::TX,
+        }
+    }
+
+    /* Zero-based index of the channel within its direction */
+    pub fn index(&self) -> u8 {
+        *self as u8 >> 1
+    }
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum BladerfChannelLayout {
+    RxX1,
+    TxX1,
+    RxX2,
+    TxX2,
+}
+
+impl BladerfChannelLayout {
+    pub fn direction(&self) -> BladerfDirection {
+        match self {
+            BladerfChannelLayout::RxX1 | BladerfChannelLayout::RxX2 => BladerfDirection::RX,
+            BladerfChannelLayout::TxX1 | BladerfChannelLayout::TxX2 => BladerfDirection::TX,
+        }
+    }
+
+    pub fn num_channels(&self) -> usize {
+        match self {
+            BladerfChannelLayout::RxX1 | BladerfChannelLayout::TxX1 => 1,
+            BladerfChannelLayout::RxX2 | BladerfChannelLayout::TxX2 => 2,
+        }
+    }
+
+    pub fn channels(&self) -> &'static [BladerfChannel] {
+        match self {
+            BladerfChannelLayout::RxX1 => &[BladerfChannel::RX1],
+            BladerfChannelLayout::TxX1 => &[BladerfChannel::TX1],
+            BladerfChannelLayout::RxX2 => &[BladerfChannel::RX1, BladerfChannel::RX2],
+            BladerfChannelLayout::TxX2 => &[BladerfChannel::TX1, BladerfChannel::TX2],
+        }
+    }
+}
+
 pub struct Device {
     pub(crate) vendor_id: u16,
     pub(crate) product_id: u16,
@@ -47,6 +131,30 @@ pub struct Device {
 
     #[cfg(feature = "nusb")]
     pub(crate) device: DeviceInfo,
+
+    /* Keeps each NIOS request paired with its response when streams share the device */
+    #[cfg(feature = "std")]
+    pub(crate) nios_lock: futures::lock::Mutex<()>,
+
+    /* Serializes read-modify-write cycles on the FPGA config register */
+    #[cfg(feature = "std")]
+    pub(crate) config_lock: futures::lock::Mutex<()>,
+
+    /* bladeRF 2.0: next NIOS quick-tune profile slot, per direction */
+    #[cfg(feature = "std")]
+    pub(crate) quick_tune_profiles: [core::sync::atomic::AtomicU16; 2],
+
+    /* bladeRF 2.0: the FPGA 8-bit mode as it was before oversampling was enabled */
+    #[cfg(feature = "std")]
+    pub(crate) oversample_eight_bit: core::sync::atomic::AtomicBool,
+
+    /* Retunes scheduled through this host and not yet due, per direction */
+    #[cfg(feature = "std")]
+    pub(crate) retune_queues: [futures::lock::Mutex<retune::queue::RetuneQueue>; 2],
+
+    /* bladeRF 1: LNA/PA pinned by the user per direction, applied by tuning instead of the band's default */
+    #[cfg(feature = "std")]
+    pub(crate) rf_path_manual: [core::sync::atomic::AtomicU8; 2],
 }
 
 
@@ -60,6 +168,15 @@ impl Device {
         self.interface.is_some()
     }
 
+    /* Board-specific code must not guess, so an unknown product ID is refused */
+    pub fn board(&self) -> anyhow::Result<BladerfBoard> {
+        match self.product_id {
+            BLADERF1_PRODUCT_ID => Ok(BladerfBoard::BladeRF1),
+            BLADERF2_PRODUCT_ID => Ok(BladerfBoard::BladeRF2),
+            _ => Err(BladerfError::Unsupported.into()),
+        }
+    }
+
     pub async fn connect(&mut self) -> anyhow::Result<()> {
         // Connect to the device
         self.interface = Some(self.device.open()?.claim_interface(0)?);
@@ -67,7 +184,7 @@ impl Device {
         Ok(())
     }
 
-    pub async fn enable_rx(&mut self) -> anyhow::Result<()> {
+    pub async fn enable_rx(&self) -> anyhow::Result<()> {
         let test = control_device_to_host::<BLADE_USB_CMD_RF_RX, 1, 0, 4>(self).await?;
 
         if test == [0, 0, 0, 0] {
@@ -77,7 +194,7 @@ impl Device {
         }
     }
 
-    pub async fn disable_rx(&mut self) -> anyhow::Result<()> {
+    pub async fn disable_rx(&self) -> anyhow::Result<()> {
         let test = control_device_to_host::<BLADE_USB_CMD_RF_RX, 0, 0, 4>(self).await?;
 
         if test == [64, 0, 0, 0] {
@@ -94,18 +211,72 @@ impl Device {
         if test == [0, 0, 0, 0] {
             Ok(())
         } else {
-            Err(anyhow::anyhow!("Error enabling RX"))
+            Err(anyhow::anyhow!("Error enabling TX"))
         }
     }
 
-    pub async fn disable_tx(&mut self) -> anyhow::Result<()> {
+    pub async fn disable_tx(&self) -> anyhow::Result<()> {
         let test = control_device_to_host::<BLADE_USB_CMD_RF_TX, 0, 0, 4>(self).await?;
 
         if test == [64, 0, 0, 0] {
             Ok(())
         } else {
-            Err(anyhow::anyhow!("Error disabling RX"))
+            Err(anyhow::anyhow!("Error disabling TX"))
+        }
+    }
+
+    /* bladeRF 2.0: route a channel into the FPGA sample stream */
+    pub async fn enable_channel(&self, ch: BladerfChannel, enable: bool) -> anyhow::Result<()> {
+        if self.board()? != BladerfBoard::BladeRF2 {
+            return Err(BladerfError::Unsupported.into());
         }
+
+        let bit = 1 << (RFFE_CONTROL_MIMO_RX_EN_0 + ch as u32);
+        let reg = nios_rffe_control_read(self).await?;
+
+        let reg = if enable { reg | bit } else { reg & !bit };
+        nios_rffe_control_write(self, reg).await?;
+
+        Ok(())
+    }
+
+    /* bladeRF 2.0: enable exactly the channels of a layout in its direction */
+    pub async fn set_channel_layout(&self, layout: BladerfChannelLayout) -> anyhow::Result<()> {
+        if self.board()? != BladerfBoard::BladeRF2 {
+            return Err(BladerfError::Unsupported.into());
+        }
+
+        let mut reg = nios_rffe_control_read(self).await?;
+
+        for ch in [BladerfChannel::RX1, BladerfChannel::TX1, BladerfChannel::RX2, BladerfChannel::TX2] {
+            if ch.direction() != layout.direction() {
+                continue;
+            }
+
+            let bit = 1 << (RFFE_CONTROL_MIMO_RX_EN_0 + ch as u32);
+
+            if layout.channels().contains(&ch) {
+                reg |= bit;
+            } else {
+                reg &= !bit;
+            }
+        }
+
+        nios_rffe_control_write(self, reg).await?;
+
+        Ok(())
+    }
+
+    #[cfg(feature = "std")]
+    pub async fn set_rx_mux(&self, mux: RxMux) -> anyhow::Result<()> {
+        fpga_config_modify(self, |reg| reg.set_rx_mux(mux)).await?;
+
+        Ok(())
+    }
+
+    #[cfg(feature = "std")]
+    pub async fn get_rx_mux(&self) -> anyhow::Result<RxMux> {
+        fpga_config_read(self).await?.rx_mux()
     }
 
     pub async fn get_version(&mut self) -> anyhow::Result<BladerfVersion> {
@@ -118,12 +289,6 @@ impl Device {
         })
     }
     
-    pub async fn get_gain(&mut self, bladerf_direction: BladerfDirection) -> anyhow::Result<f32> {
-        let lna = nios_lms6_read(self, 0x75).await?;
-
-        Ok(lna as f32)
-    }
-
     pub fn disconnect(&mut self) -> anyhow::Result<()> {
         // Disconnect from the device
         self.interface = None;
@@ -0,0 +1,206 @@
+use crate::lms6::gain::*;
+use crate::lms6::*;
+use anyhow::Result;
+
+/* Polls of DC_CLBR_DONE before a calibration is considered stuck */
+const DC_CAL_MAX_POLLS: usize = 25;
+/* Starting DC_CNTVAL, the middle of the 6-bit range */
+const DC_CAL_CNTVAL: u8 = 31;
+const DC_REGVAL_MAX: u8 = 0x3f;
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum DcCalModule {
+    LpfTuning,
+    TxLpf,
+    RxLpf,
+    RxVga2,
+}
+
+impl DcCalModule {
+    /* Base address of the module's DC calibration registers */
+    pub fn base(&self) -> u8 {
+        match self {
+            DcCalModule::LpfTuning => 0x00,
+            DcCalModule::TxLpf => 0x30,
+            DcCalModule::RxLpf => 0x50,
+            DcCalModule::RxVga2 => 0x60,
+        }
+    }
+
+    /* Calibration clock in the clock enable register */
+    pub fn clock(&self) -> u8 {
+        match self {
+            DcCalModule::LpfTuning => 1 << 5,
+            DcCalModule::TxLpf => 1 << 1,
+            DcCalModule::RxLpf => 1 << 3,
+            DcCalModule::RxVga2 => 1 << 4,
+        }
+    }
+
+    pub fn submodules(&self) -> u8 {
+        match self {
+            DcCalModule::LpfTuning => 1,
+            DcCalModule::TxLpf | DcCalModule::RxLpf => 2,
+            DcCalModule::RxVga2 => 5,
+        }
+    }
+
+    /* Register and power-down bits of the module's DC comparators, which are kept off outside a calibration */
+    pub fn comparators(&self) -> Option<(u8, u8)> {
+        match self {
+            DcCalModule::LpfTuning => None,
+            DcCalModule::TxLpf => Some((0x3f, 1 << 7)),
+            DcCalModule::RxLpf => Some((0x5f, 1 << 7)),
+            DcCalModule::RxVga2 => Some((0x6e, 0x3 << 6)),
+        }
+    }
+
+    pub fn is_rx(&self) -> bool {
+        matches!(self, DcCalModule::RxLpf | DcCalModule::RxVga2)
+    }
+}
+
+/* State changed by a calibration and put back afterwards */
+struct DcCalState {
+    clk_en: u8,
+    /* LNA, RXVGA1 and RXVGA2 gains, saved for the RX modules only */
+    rx_gains: Option<(LnaGain, i32, i32)>,
+}
+
+#[cfg(feature = "nusb")]
+impl Device {
+    /*
+     * bladeRF 1: run the LMS6002D DC offset calibration of one module. The clock
+     * enables, the comparators and for the RX modules the gains are restored
+     * afterwards even if the calibration fails. An RX submodule still saturated at
+     * minimum gain fails the calibration rather than keeping a trim that does not
+     * correct the offset (libbladerf logs it and carries on); the chip is left
+     * with the trim of that last attempt. A calibration loop that never reports
+     * completion fails with BladerfError::Timeout.
+     */
+    pub async fn calibrate_dc(&self, module: DcCalModule) -> Result<()> {
+        lms_check_board(self)?;
+
+        let rx_gains = if module.is_rx() {
+            Some((self.get_lna_gain().await?, self.get_rxvga1().await?, self.get_rxvga2().await?))
+        } else {
+            None
+        };
+
+        let state = DcCalState {
+            clk_en: nios_lms6_read(self, LMS_REG_CLK_EN).await?,
+            rx_gains,
+        };
+
+        let result = self.dc_cal_run(module, &state).await;
+
+        /* Run every restore step, keeping the first failure */
+        let mut restored = self.dc_cal_deinit(module).await;
+        restored = restored.and(nios_lms6_write(self, LMS_REG_CLK_EN, state.clk_en).await.map(drop));
+
+        if let Some((lna, rxvga1, rxvga2)) = state.rx_gains {
+            restored = restored.and(self.set_lna_gain(lna).await);
+            restored = restored.and(self.set_rxvga1(rxvga1).await);
+            restored = restored.and(self.set_rxvga2(rxvga2).await);
+        }
+
+        result.and(restored)
+    }
+
+    async fn dc_cal_run(&self, module: DcCalModule, state: &DcCalState) -> Result<()> {
+        nios_lms6_write(self, LMS_REG_CLK_EN, state.clk_en | module.clock()).await?;
+
+        match module {
+            DcCalModule::LpfTuning => {}
+            /* Calibrate at full gain so the comparators see the offset at its largest */
+            DcCalModule::RxLpf | DcCalModule::RxVga2 => {
+                self.set_lna_gain(LnaGain::Max).await?;
+                self.set_rxvga1(BLADERF_RXVGA1_GAIN_MAX).await?;
+                self.set_rxvga2(BLADERF_RXVGA2_GAIN_MAX).await?;
+            }
+            DcCalModule::TxLpf => {
+                /* The DAC must not add DC of its own */
+                lms_modify(self, 0x36, 1 << 7, 1 << 7).await?;
+            }
+        }
+
+        if let Some((addr, mask)) = module.comparators() {
+            lms_modify(self, addr, mask, 0).await?;
+        }
+
+        let mut rxvga1 = BLADERF_RXVGA1_GAIN_MAX;
+        let mut rxvga2 = BLADERF_RXVGA2_GAIN_MAX;
+
+        for submodule in 0..module.submodules() {
+            loop {
+                let dc_regval = self.dc_cal_loop(module.base(), submodule, DC_CAL_CNTVAL).await?;
+
+                /* A result on either rail means the offset was out of range; retry with less gain */
+                let saturated = dc_regval == 0 || dc_regval == DC_REGVAL_MAX;
+
+                if !saturated || !module.is_rx() {
+                    if module == DcCalModule::LpfTuning {
+                        /* The tuning result trims both LPFs */
+                        lms_modify(self, 0x35, DC_REGVAL_MAX, dc_regval).await?;
+                        lms_modify(self, 0x55, DC_REGVAL_MAX, dc_regval).await?;
+                    }
+
+                    break;
+                }
+
+                if module == DcCalModule::RxVga2 && rxvga2 > BLADERF_RXVGA2_GAIN_MIN {
+                    rxvga2 -= 3;
+                    self.set_rxvga2(rxvga2).await?;
+                } else if rxvga1 > BLADERF_RXVGA1_GAIN_MIN {
+                    rxvga1 -= 1;
+                    self.set_rxvga1(rxvga1).await?;
+                } else {
+                    return Err(BladerfError::Unexpected.into());
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /* Power the comparators down and, for the TX LPF, the DAC back up */
+    async fn dc_cal_deinit(&self, module: DcCalModule) -> Result<()> {
+        let mut result = Ok(());
+
+        if let Some((addr, mask)) = module.comparators() {
+            result = lms_modify(self, addr, mask, mask).await;
+        }
+
+        if module == DcCalModule::TxLpf {
+            result = result.and(lms_modify(self, 0x36, 1 << 7, 0).await);
+        }
+
+        result
+    }
+
+    /* Calibrate one submodule and return its DC_REGVAL */
+    async fn dc_cal_loop(&self, base: u8, submodule: u8, cntval: u8) -> Result<u8> {
+        /* Select the submodule, then latch DC_CNTVAL with DC_LOAD */
+        let mut ctrl = nios_lms6_read(self, base + 3).await?;
+        ctrl = (ctrl & !0x07) | (submodule & 0x07);
+        nios_lms6_write(self, base + 3, ctrl).await?;
+
+        nios_lms6_write(self, base + 2, cntval).await?;
+        nios_lms6_write(self, base + 3, ctrl | (1 << 4)).await?;
+        nios_lms6_write(self, base + 3, ctrl).await?;
+
+        /* Pulse DC_START_CLBR */
+        nios_lms6_write(self, base + 3, ctrl | (1 << 5)).await?;
+        nios_lms6_write(self, base + 3, ctrl).await?;
+
+        for _ in 0..DC_CAL_MAX_POLLS {
+            /* DC_CLBR_DONE is active low */
+            if nios_lms6_read(self, base + 1).await? & (1 << 1) == 0 {
+                /* DC_LOCK is unreliable, so report DC_REGVAL itself */
+                return Ok(nios_lms6_read(self, base).await? & DC_REGVAL_MAX);
+            }
+        }
+
+        Err(BladerfError::Timeout.into())
+    }
+}
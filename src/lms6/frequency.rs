@@ -0,0 +1,285 @@
+use crate::error::BladerfError;
+use crate::lms6::*;
+use crate::nios::fpga_config::fpga_config_modify;
+use anyhow::{Error, Result};
+use core::future::Future;
+use core::time::Duration;
+
+pub const LMS_REFERENCE_HZ: u64 = 38_400_000;
+
+pub const BLADERF1_FREQUENCY_MIN: u64 = 237_500_000;
+pub const BLADERF1_FREQUENCY_MAX: u64 = 3_800_000_000;
+/* Tunings below this use the low band LNA/PA paths */
+pub const BLADERF1_BAND_HIGH: u64 = 1_500_000_000;
+
+pub const VCOCAP_MAX_VALUE: u8 = 0x3f;
+const VCOCAP_EST_MIN: u64 = 15;
+const VCOCAP_EST_MAX: u64 = 55;
+
+/* Settling time of the VTUNE comparators after a VCOCAP change, as libbladerf's VTUNE_DELAY_SMALL */
+const VTUNE_DELAY: Duration = Duration::from_micros(50);
+
+/* VTUNE comparator states, read from bits 7:6 of PLL register 10 */
+pub const VCO_NORM: u8 = 0x00;
+pub const VCO_LOW: u8 = 0x01;
+pub const VCO_HIGH: u8 = 0x02;
+
+/* FREQSEL for each VCO band, as (lowest Hz, highest Hz, FREQSEL) */
+const BANDS: [(u64, u64, u8); 16] = [
+    (BLADERF1_FREQUENCY_MIN, 285_625_000, 0x27),
+    (285_625_000, 336_875_000, 0x2f),
+    (336_875_000, 405_000_000, 0x37),
+    (405_000_000, 465_000_000, 0x3f),
+    (465_000_000, 571_250_000, 0x26),
+    (571_250_000, 673_750_000, 0x2e),
+    (673_750_000, 810_000_000, 0x36),
+    (810_000_000, 930_000_000, 0x3e),
+    (930_000_000, 1_142_500_000, 0x25),
+    (1_142_500_000, 1_347_500_000, 0x2d),
+    (1_347_500_000, 1_620_000_000, 0x35),
+    (1_620_000_000, 1_860_000_000, 0x3d),
+    (1_860_000_000, 2_285_000_000, 0x24),
+    (2_285_000_000, 2_695_000_000, 0x2c),
+    (2_695_000_000, 3_240_000_000, 0x34),
+    (3_240_000_000, BLADERF1_FREQUENCY_MAX, 0x3c),
+];
+
+/*
+ * LMS6002D PLL settings for one frequency:
+ * f = (nint + nfrac / 2^23) * LMS_REFERENCE_HZ / x, with x = 2^((freqsel & 7) - 3)
+ */
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Default)]
+pub struct LmsFreq {
+    pub freqsel: u8,
+    pub vcocap: u8,
+    pub nint: u16,
+    pub nfrac: u32,
+    pub low_band: bool,
+}
+
+impl LmsFreq {
+    pub fn from_frequency(hz: u64) -> Result<Self> {
+        let &(low, high, freqsel) = BANDS
+            .iter()
+            .find(|&&(low, high, _)| hz >= low && hz <= high)
+            .ok_or(BladerfError::Inval)?;
+
+        let x = vco_divider(freqsel);
+        let nint = x * hz / LMS_REFERENCE_HZ;
+        let nfrac = ((1 << 23) * (x * hz - nint * LMS_REFERENCE_HZ) + LMS_REFERENCE_HZ / 2) / LMS_REFERENCE_HZ;
+
+        Ok(LmsFreq {
+            freqsel,
+            vcocap: estimate_vcocap(hz, low, high),
+            nint: nint as u16,
+            nfrac: nfrac as u32,
+            low_band: hz < BLADERF1_BAND_HIGH,
+        })
+    }
+
+    /* Frequency these settings synthesize, rounded to the nearest Hz */
+    pub fn frequency(&self) -> u64 {
+        let pll_coeff = ((self.nint as u64) << 23) + self.nfrac as u64;
+        let div = vco_divider(self.freqsel) << 23;
+
+        (LMS_REFERENCE_HZ * pll_coeff + div / 2) / div
+    }
+}
+
+fn vco_divider(freqsel: u8) -> u64 {
+    1 << ((freqsel & 7).saturating_sub(3))
+}
+
+/* libbladerf's linear estimate: VCOCAP rises from EST_MIN at the low end of the band to EST_MAX at the top */
+fn estimate_vcocap(hz: u64, low: u64, high: u64) -> u8 {
+    let range = VCOCAP_EST_MAX - VCOCAP_EST_MIN;
+    let vcocap = VCOCAP_EST_MIN + range * (hz - low) / (high - low);
+
+    vcocap.min(VCOCAP_MAX_VALUE as u64) as u8
+}
+
+/*
+ * Find the VCOCAP value in the middle of the window where the VTUNE comparators
+ * both read low. `probe` writes a VCOCAP value and returns the VTUNE state for
+ * it. VTUNE reads HIGH below the window and LOW above it, so the search walks
+ * from `start` towards the window and then out to both of its edges.
+ */
+pub async fn search_vcocap<F, Fut>(start: u8, mut probe: F) -> Result<u8>
+where
+    F: FnMut(u8) -> Fut,
+    Fut: Future<Output = Result<u8>>,
+{
+    let unlocked = || Error::from(BladerfError::Unexpected);
+
+    let mut vcocap = start.min(VCOCAP_MAX_VALUE);
+    let mut vtune = probe(vcocap).await?;
+    let first = vtune;
+
+    while vtune != VCO_NORM {
+        /* Reversing direction means we stepped over the window without seeing it */
+        if vtune != first {
+            return Err(unlocked());
+        }
+
+        vcocap = match vtune {
+            VCO_HIGH if vcocap < VCOCAP_MAX_VALUE => vcocap + 1,
+            VCO_LOW if vcocap > 0 => vcocap - 1,
+            _ => return Err(unlocked()),
+        };
+        vtune = probe(vcocap).await?;
+    }
+
+    let mut low = vcocap;
+    while low > 0 && probe(low - 1).await? == VCO_NORM {
+        low -= 1;
+    }
+
+    let mut high = vcocap;
+    while high < VCOCAP_MAX_VALUE && probe(high + 1).await? == VCO_NORM {
+        high += 1;
+    }
+
+    let vcocap = low + (high - low) / 2;
+
+    if probe(vcocap).await? != VCO_NORM {
+        return Err(unlocked());
+    }
+
+    Ok(vcocap)
+}
+
+#[cfg(feature = "nusb")]
+impl Device {
+    /* bladeRF 1: tune a channel and return the frequency the PLL was actually set to */
+    pub async fn set_frequency(&self, channel: BladerfChannel, hz: u64) -> Result<u64> {
+        lms_check_board(self)?;
+
+        let dir = lms_direction(channel)?;
+        let freq = LmsFreq::from_frequency(hz)?;
+
+        self.lms_set_precalculated_frequency(dir, &freq, false).await?;
+        self.lms_select_band(dir, freq.low_band).await?;
+
+        self.get_frequency(channel).await
+    }
+
+    pub async fn get_frequency(&self, channel: BladerfChannel) -> Result<u64> {
+        lms_check_board(self)?;
+
+        let freq = self.lms_get_tuning(lms_direction(channel)?).await?;
+
+        Ok(freq.frequency())
+    }
+
+    pub(crate) async fn lms_get_tuning(&self, dir: BladerfDirection) -> Result<LmsFreq> {
+        let base = lms_pll_base(dir);
+        let mut regs = [0u8; 4];
+
+        for (i, reg) in regs.iter_mut().enumerate() {
+            *reg = nios_lms6_read(self, base + i as u8).await?;
+        }
+
+        let freqsel = nios_lms6_read(self, base + 5).await? >> 2;
+        let vcocap = nios_lms6_read(self, base + 9).await? & VCOCAP_MAX_VALUE;
+
+        let freq = LmsFreq {
+            freqsel,
+            vcocap,
+            nint: ((regs[0] as u16) << 1) | (regs[1] >> 7) as u16,
+            nfrac: ((regs[1] as u32 & 0x7f) << 16) | ((regs[2] as u32) << 8) | regs[3] as u32,
+            low_band: false,
+        };
+
+        Ok(LmsFreq {
+            low_band: freq.frequency() < BLADERF1_BAND_HIGH,
+            ..freq
+        })
+    }
+
+    /* bladeRF 1: both VTUNE comparators low means the PLL is locked */
+    pub async fn is_pll_locked(&self, channel: BladerfChannel) -> Result<bool> {
+        lms_check_board(self)?;
+
+        Ok(self.lms_get_vtune(lms_direction(channel)?).await? == VCO_NORM)
+    }
+
+    async fn lms_get_vtune(&self, dir: BladerfDirection) -> Result<u8> {
+        Ok(nios_lms6_read(self, lms_pll_base(dir) + 10).await? >> 6)
+    }
+
+    /*
+     * Write PLL register 9 and read VTUNE once it has settled. The delay is short
+     * enough to block on, and there is no async timer in the dependency tree.
+     */
+    async fn lms_probe_vcocap(&self, dir: BladerfDirection, reg: u8) -> Result<u8> {
+        nios_lms6_write(self, lms_pll_base(dir) + 9, reg).await?;
+        std::thread::sleep(VTUNE_DELAY);
+
+        self.lms_get_vtune(dir).await
+    }
+
+    /*
+     * Program the PLL. Unless `force_vcocap` is set, VCOCAP is searched starting
+     * from the estimate in `freq`. Returns the settings with the VCOCAP in use,
+     * and fails if the synthesizer does not lock.
+     */
+    pub(crate) async fn lms_set_precalculated_frequency(&self, dir: BladerfDirection, freq: &LmsFreq, force_vcocap: bool) -> Result<LmsFreq> {
+        let base = lms_pll_base(dir);
+
+        /* The DSM registers only latch while their clocks run */
+        let clk_en = nios_lms6_read(self, LMS_REG_CLK_EN).await?;
+        nios_lms6_write(self, LMS_REG_CLK_EN, clk_en | 0x05).await?;
+
+        /* FREQSEL and the PLL output buffer for the band; loopback keeps its own buffer selection */
+        let selout = if self.is_loopback_enabled().await? {
+            nios_lms6_read(self, base + 5).await? & 0x03
+        } else if freq.low_band {
+            1
+        } else {
+            2
+        };
+        nios_lms6_write(self, base + 5, (freq.freqsel << 2) | selout).await?;
+
+        nios_lms6_write(self, base, (freq.nint >> 1) as u8).await?;
+        nios_lms6_write(self, base + 1, (((freq.nint & 1) << 7) as u8) | ((freq.nfrac >> 16) & 0x7f) as u8).await?;
+        nios_lms6_write(self, base + 2, (freq.nfrac >> 8) as u8).await?;
+        nios_lms6_write(self, base + 3, freq.nfrac as u8).await?;
+
+        let vcocap_reg = nios_lms6_read(self, base + 9).await? & !VCOCAP_MAX_VALUE;
+        let vcocap = if force_vcocap {
+            Ok(freq.vcocap)
+        } else {
+            search_vcocap(freq.vcocap, |vcocap| self.lms_probe_vcocap(dir, vcocap_reg | vcocap)).await
+        };
+
+        /* Stop the DSM clocks again even when the search failed */
+        nios_lms6_write(self, LMS_REG_CLK_EN, clk_en & !0x05).await?;
+
+        let tuned = LmsFreq { vcocap: vcocap?, ..*freq };
+
+        if self.lms_probe_vcocap(dir, vcocap_reg | tuned.vcocap).await? != VCO_NORM {
+            return Err(BladerfError::Unexpected.into());
+        }
+
+        Ok(tuned)
+    }
+
+    /* Route the LNA or PA and set the matching FPGA band select */
+    async fn lms_select_band(&self, dir: BladerfDirection, low_band: bool) -> Result<()> {
+        /* Loopback owns the LNA and PA selection until it is turned off */
+        if !self.is_loopback_enabled().await? {
+            self.lms_select_band_path(dir, low_band).await?;
+        }
+
+        /* 1 = high band, 2 = low band */
+        let band = if low_band { 2 } else { 1 };
+
+        fpga_config_modify(self, |reg| match dir {
+            BladerfDirection::TX => reg.tx_band = band,
+            BladerfDirection::RX => reg.rx_band = band,
+        })
+        .await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,211 @@
+use crate::error::BladerfError;
+use crate::lms6::*;
+use anyhow::Result;
+
+pub const BLADERF_LNA_GAIN_MID_DB: i32 = 3;
+pub const BLADERF_LNA_GAIN_MAX_DB: i32 = 6;
+
+pub const BLADERF_RXVGA1_GAIN_MIN: i32 = 5;
+pub const BLADERF_RXVGA1_GAIN_MAX: i32 = 30;
+pub const BLADERF_RXVGA2_GAIN_MIN: i32 = 0;
+pub const BLADERF_RXVGA2_GAIN_MAX: i32 = 30;
+pub const BLADERF_TXVGA1_GAIN_MIN: i32 = -35;
+pub const BLADERF_TXVGA1_GAIN_MAX: i32 = -4;
+pub const BLADERF_TXVGA2_GAIN_MIN: i32 = 0;
+pub const BLADERF_TXVGA2_GAIN_MAX: i32 = 25;
+
+pub const BLADERF_RX_GAIN_MIN: i32 = BLADERF_RXVGA1_GAIN_MIN;
+pub const BLADERF_RX_GAIN_MAX: i32 = BLADERF_LNA_GAIN_MAX_DB + BLADERF_RXVGA1_GAIN_MAX + BLADERF_RXVGA2_GAIN_MAX;
+pub const BLADERF_TX_GAIN_MIN: i32 = 0;
+pub const BLADERF_TX_GAIN_MAX: i32 = BLADERF_TXVGA1_GAIN_MAX - BLADERF_TXVGA1_GAIN_MIN + BLADERF_TXVGA2_GAIN_MAX;
+
+/* RXVGA1 code for each gain from 0 to 30 dB; the stage is far from linear in its code */
+const RXVGA1_LUT_VAL2CODE: [u8; 31] = [
+    2, 2, 2, 2, 2, 2, 14, 26, 37, 47, 56, 63, 70, 76, 82, 87, 91, 95, 99, 102, 104, 107, 109, 111, 113, 114, 116, 117, 118, 119, 120,
+];
+
+#[repr(u8)]
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum LnaGain {
+    Bypass = 1,
+    Mid = 2,
+    Max = 3,
+}
+
+impl LnaGain {
+    pub fn db(&self) -> i32 {
+        match self {
+            LnaGain::Bypass => 0,
+            LnaGain::Mid => BLADERF_LNA_GAIN_MID_DB,
+            LnaGain::Max => BLADERF_LNA_GAIN_MAX_DB,
+        }
+    }
+}
+
+impl TryFrom<u8> for LnaGain {
+    type Error = anyhow::Error;
+
+    fn try_from(value: u8) -> Result<Self> {
+        match value {
+            1 => Ok(LnaGain::Bypass),
+            2 => Ok(LnaGain::Mid),
+            3 => Ok(LnaGain::Max),
+            _ => Err(BladerfError::Unexpected.into()),
+        }
+    }
+}
+
+pub fn rxvga1_code(db: i32) -> u8 {
+    RXVGA1_LUT_VAL2CODE[db.clamp(BLADERF_RXVGA1_GAIN_MIN, BLADERF_RXVGA1_GAIN_MAX) as usize]
+}
+
+/* Highest gain whose code does not exceed `code` */
+pub fn rxvga1_db(code: u8) -> i32 {
+    (BLADERF_RXVGA1_GAIN_MIN..=BLADERF_RXVGA1_GAIN_MAX)
+        .rev()
+        .find(|&db| RXVGA1_LUT_VAL2CODE[db as usize] <= code)
+        .unwrap_or(BLADERF_RXVGA1_GAIN_MIN)
+}
+
+/* Split an overall RX gain the way libbladerf does: LNA first, then RXVGA1, the rest in RXVGA2 */
+pub fn rx_gain_stages(gain: i32) -> (LnaGain, i32, i32) {
+    let mut gain = gain.clamp(BLADERF_RX_GAIN_MIN, BLADERF_RX_GAIN_MAX);
+
+    let lna = if gain <= BLADERF_RXVGA1_GAIN_MIN {
+        LnaGain::Bypass
+    } else if gain <= BLADERF_RXVGA1_GAIN_MIN + BLADERF_LNA_GAIN_MID_DB {
+        LnaGain::Mid
+    } else {
+        LnaGain::Max
+    };
+    gain -= lna.db();
+
+    let rxvga1 = gain.clamp(BLADERF_RXVGA1_GAIN_MIN, BLADERF_RXVGA1_GAIN_MAX);
+    gain -= rxvga1;
+
+    /* RXVGA2 moves in 3 dB steps */
+    let rxvga2 = (gain - gain % 3).clamp(BLADERF_RXVGA2_GAIN_MIN, BLADERF_RXVGA2_GAIN_MAX);
+
+    (lna, rxvga1, rxvga2)
+}
+
+/* TX gain counts from TXVGA1 at its minimum; TXVGA2 is used up before TXVGA1 is raised */
+pub fn tx_gain_stages(gain: i32) -> (i32, i32) {
+    let gain = gain.clamp(BLADERF_TX_GAIN_MIN, BLADERF_TX_GAIN_MAX);
+
+    if gain <= BLADERF_TXVGA2_GAIN_MAX {
+        (BLADERF_TXVGA1_GAIN_MIN, gain)
+    } else {
+        (BLADERF_TXVGA1_GAIN_MIN + gain - BLADERF_TXVGA2_GAIN_MAX, BLADERF_TXVGA2_GAIN_MAX)
+    }
+}
+
+fn check_range(db: i32, min: i32, max: i32) -> Result<()> {
+    if db < min || db > max {
+        return Err(BladerfError::Inval.into());
+    }
+
+    Ok(())
+}
+
+#[cfg(feature = "nusb")]
+impl Device {
+    pub async fn set_lna_gain(&self, gain: LnaGain) -> Result<()> {
+        lms_check_board(self)?;
+
+        lms_modify(self, 0x75, 0xc0, (gain as u8) << 6).await
+    }
+
+    pub async fn get_lna_gain(&self) -> Result<LnaGain> {
+        lms_check_board(self)?;
+
+        LnaGain::try_from(nios_lms6_read(self, 0x75).await? >> 6)
+    }
+
+    pub async fn set_rxvga1(&self, db: i32) -> Result<()> {
+        lms_check_board(self)?;
+
+        check_range(db, BLADERF_RXVGA1_GAIN_MIN, BLADERF_RXVGA1_GAIN_MAX)?;
+
+        lms_modify(self, 0x76, 0x7f, rxvga1_code(db)).await
+    }
+
+    pub async fn get_rxvga1(&self) -> Result<i32> {
+        lms_check_board(self)?;
+
+        Ok(rxvga1_db(nios_lms6_read(self, 0x76).await? & 0x7f))
+    }
+
+    pub async fn set_rxvga2(&self, db: i32) -> Result<()> {
+        lms_check_board(self)?;
+
+        check_range(db, BLADERF_RXVGA2_GAIN_MIN, BLADERF_RXVGA2_GAIN_MAX)?;
+
+        lms_modify(self, 0x65, 0x1f, (db / 3) as u8).await
+    }
+
+    pub async fn get_rxvga2(&self) -> Result<i32> {
+        lms_check_board(self)?;
+
+        Ok((nios_lms6_read(self, 0x65).await? & 0x1f) as i32 * 3)
+    }
+
+    pub async fn set_txvga1(&self, db: i32) -> Result<()> {
+        lms_check_board(self)?;
+
+        check_range(db, BLADERF_TXVGA1_GAIN_MIN, BLADERF_TXVGA1_GAIN_MAX)?;
+
+        lms_modify(self, 0x41, 0x1f, (db - BLADERF_TXVGA1_GAIN_MIN) as u8).await
+    }
+
+    pub async fn get_txvga1(&self) -> Result<i32> {
+        lms_check_board(self)?;
+
+        Ok((nios_lms6_read(self, 0x41).await? & 0x1f) as i32 + BLADERF_TXVGA1_GAIN_MIN)
+    }
+
+    pub async fn set_txvga2(&self, db: i32) -> Result<()> {
+        lms_check_board(self)?;
+
+        check_range(db, BLADERF_TXVGA2_GAIN_MIN, BLADERF_TXVGA2_GAIN_MAX)?;
+
+        lms_modify(self, 0x45, 0xf8, (db as u8) << 3).await
+    }
+
+    pub async fn get_txvga2(&self) -> Result<i32> {
+        lms_check_board(self)?;
+
+        /* Codes above 25 dB are documented as reserved and behave as 25 dB */
+        Ok((((nios_lms6_read(self, 0x45).await? >> 3) & 0x1f) as i32).min(BLADERF_TXVGA2_GAIN_MAX))
+    }
+
+    /* bladeRF 1: overall gain in dB, spread over the stages of the channel's direction */
+    pub async fn set_gain(&self, channel: BladerfChannel, gain: i32) -> Result<()> {
+        lms_check_board(self)?;
+
+        match lms_direction(channel)? {
+            BladerfDirection::RX => {
+                let (lna, rxvga1, rxvga2) = rx_gain_stages(gain);
+
+                self.set_lna_gain(lna).await?;
+                self.set_rxvga1(rxvga1).await?;
+                self.set_rxvga2(rxvga2).await
+            }
+            BladerfDirection::TX => {
+                let (txvga1, txvga2) = tx_gain_stages(gain);
+
+                self.set_txvga1(txvga1).await?;
+                self.set_txvga2(txvga2).await
+            }
+        }
+    }
+
+    pub async fn get_gain(&self, channel: BladerfChannel) -> Result<i32> {
+        lms_check_board(self)?;
+
+        match lms_direction(channel)? {
+            BladerfDirection::RX => Ok(self.get_lna_gain().await?.db() + self.get_rxvga1().await? + self.get_rxvga2().await?),
+            BladerfDirection::TX => Ok(self.get_txvga1().await? - BLADERF_TXVGA1_GAIN_MIN + self.get_txvga2().await?),
+        }
+    }
+}
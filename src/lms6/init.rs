@@ -0,0 +1,65 @@
+use crate::lms6::gain::*;
+use crate::lms6::*;
+use anyhow::Result;
+
+/* Default tuning after initialization, as libbladerf uses */
+pub const BLADERF1_DEFAULT_TX_FREQUENCY: u64 = 2_447_000_000;
+pub const BLADERF1_DEFAULT_RX_FREQUENCY: u64 = 2_484_000_000;
+
+#[cfg(feature = "nusb")]
+impl Device {
+    /*
+     * bladeRF 1: bring the LMS6002D into a known state after connect(), whatever
+     * the previous user of the board left behind. Follows the libbladerf open
+     * sequence, including the register tweaks recommended in the LMS6002D FAQ.
+     * Sampling mode, loopback and LPF mode are not set explicitly; the soft reset
+     * returns them to the chip defaults (internal sampling, no loopback, normal LPF).
+     * Refused with BladerfError::Unsupported on anything but a bladeRF 1.
+     */
+    pub async fn initialize(&self) -> Result<()> {
+        lms_check_board(self)?;
+
+        /* Pulse the soft reset */
+        nios_lms6_write(self, 0x05, 0x12).await?;
+        nios_lms6_write(self, 0x05, 0x32).await?;
+
+        /* FAQ: TX spurious emission, ADC performance, ADC common mode voltage and LNA gain */
+        nios_lms6_write(self, 0x47, 0x40).await?;
+        nios_lms6_write(self, 0x59, 0x29).await?;
+        nios_lms6_write(self, 0x64, 0x36).await?;
+        nios_lms6_write(self, 0x79, 0x37).await?;
+
+        /* The DC calibration comparators add artifacts; calibrate_dc powers them up only while it runs */
+        lms_modify(self, 0x3f, 0x80, 0x80).await?;
+        lms_modify(self, 0x5f, 0x80, 0x80).await?;
+        lms_modify(self, 0x6e, 0xc0, 0xc0).await?;
+
+        for dir in [BladerfDirection::TX, BladerfDirection::RX] {
+            self.lms_config_charge_pumps(dir).await?;
+            self.set_rf_path_auto(dir);
+        }
+
+        /* Enable the TX and RX chains */
+        nios_lms6_write(self, 0x05, 0x3e).await?;
+
+        self.set_lna_gain(LnaGain::Max).await?;
+        self.set_rxvga1(BLADERF_RXVGA1_GAIN_MAX).await?;
+        self.set_rxvga2(3).await?;
+        self.set_txvga1(-14).await?;
+        self.set_txvga2(BLADERF_TXVGA2_GAIN_MIN).await?;
+
+        self.set_frequency(BladerfChannel::TX1, BLADERF1_DEFAULT_TX_FREQUENCY).await?;
+        self.set_frequency(BladerfChannel::RX1, BLADERF1_DEFAULT_RX_FREQUENCY).await?;
+
+        Ok(())
+    }
+
+    /* PLL charge pump current (Ichp) and up/down offset currents */
+    async fn lms_config_charge_pumps(&self, dir: BladerfDirection) -> Result<()> {
+        let base = lms_pll_base(dir);
+
+        lms_modify(self, base + 6, 0x1f, 0x0c).await?;
+        lms_modify(self, base + 7, 0x1f, 0x03).await?;
+        lms_modify(self, base + 8, 0x1f, 0x00).await
+    }
+}
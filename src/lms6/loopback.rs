@@ -0,0 +1,187 @@
+use crate::error::BladerfError;
+use crate::lms6::lpf::LpfMode;
+use crate::lms6::path::*;
+use crate::lms6::*;
+use anyhow::Result;
+
+/* Loopback enable bits: LOOPBBEN in 0x46, LBEN and LBRFEN in 0x08 */
+const LOOPBBEN_MASK: u8 = 0x0c;
+const LOOPBBEN_TXLPF: u8 = 1 << 2;
+const LOOPBBEN_TXVGA: u8 = 2 << 2;
+const LBEN_MASK: u8 = 0x70;
+/* Bit 4, LBEN_OPIN, loops back to the output pins and is not used here */
+const LBEN_VGA2IN: u8 = 1 << 5;
+const LBEN_LPFIN: u8 = 1 << 6;
+const LBRFEN_MASK: u8 = 0x0f;
+
+/* bladeRF 1 LMS6002D internal loopback paths from TX back into RX */
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum Loopback {
+    None,
+    /* Baseband: TX LPF or TXVGA1 output into the RX LPF or RXVGA2 input */
+    BbTxLpfRxVga2,
+    BbTxVga1RxVga2,
+    BbTxLpfRxLpf,
+    BbTxVga1RxLpf,
+    /* RF: TX AUX PA into one of the RX LNAs */
+    RfLna1,
+    RfLna2,
+    RfLna3,
+}
+
+impl Loopback {
+    fn lna(&self) -> Option<Lna> {
+        match self {
+            Loopback::RfLna1 => Some(Lna::Lna1),
+            Loopback::RfLna2 => Some(Lna::Lna2),
+            Loopback::RfLna3 => Some(Lna::Lna3),
+            _ => None,
+        }
+    }
+}
+
+/* Loopback mode encoded by LMS registers 0x08 (LBEN, LBRFEN) and 0x46 (LOOPBBEN) */
+pub fn decode_loopback(lben_lbrf: u8, loopbben: u8) -> Loopback {
+    let baseband = |lpf, vga| match loopbben & LOOPBBEN_MASK {
+        LOOPBBEN_TXLPF => lpf,
+        LOOPBBEN_TXVGA => vga,
+        _ => Loopback::None,
+    };
+
+    match (lben_lbrf & LBEN_MASK, lben_lbrf & 0x07) {
+        (LBEN_VGA2IN, _) => baseband(Loopback::BbTxLpfRxVga2, Loopback::BbTxVga1RxVga2),
+        (LBEN_LPFIN, _) => baseband(Loopback::BbTxLpfRxLpf, Loopback::BbTxVga1RxLpf),
+        (_, 1) => Loopback::RfLna1,
+        (_, 2) => Loopback::RfLna2,
+        (_, 3) => Loopback::RfLna3,
+        _ => Loopback::None,
+    }
+}
+
+#[cfg(feature = "nusb")]
+impl Device {
+    /*
+     * bladeRF 1: route TX back into RX inside the LMS6002D. Leaving loopback with
+     * Loopback::None re-enables the RX blocks and retunes both directions so their
+     * LNA, PA and PLL output selection match the current frequency again.
+     */
+    pub async fn set_loopback(&self, mode: Loopback) -> Result<()> {
+        lms_check_board(self)?;
+
+        /* Quiet the RF ports and open the loopback switches while reconfiguring */
+        lms_select_pa(self, Pa::None).await?;
+        lms_select_lna(self, Lna::None).await?;
+        self.lms_loopback_path(Loopback::None).await?;
+
+        self.lms_loopback_rx(mode).await?;
+
+        match mode {
+            Loopback::None => {
+                let hz = self.get_frequency(BladerfChannel::TX1).await?;
+                self.set_frequency(BladerfChannel::TX1, hz).await?;
+            }
+            Loopback::RfLna1 | Loopback::RfLna2 | Loopback::RfLna3 => lms_select_pa(self, Pa::Aux).await?,
+            _ => {}
+        }
+
+        self.lms_loopback_path(mode).await
+    }
+
+    pub async fn get_loopback(&self) -> Result<Loopback> {
+        lms_check_board(self)?;
+
+        let lben_lbrf = nios_lms6_read(self, 0x08).await?;
+        let loopbben = nios_lms6_read(self, 0x46).await?;
+
+        Ok(decode_loopback(lben_lbrf, loopbben))
+    }
+
+    pub(crate) async fn is_loopback_enabled(&self) -> Result<bool> {
+        Ok(self.get_loopback().await? != Loopback::None)
+    }
+
+    async fn lms_loopback_rx(&self, mode: Loopback) -> Result<()> {
+        let lpf_enabled = self.get_lpf_mode(BladerfChannel::RX1).await? != LpfMode::Disabled;
+
+        match mode {
+            Loopback::BbTxLpfRxVga2 | Loopback::BbTxVga1RxVga2 => {
+                lms_rxvga2_enable(self, true).await?;
+
+                if lpf_enabled {
+                    self.set_lpf_mode(BladerfChannel::RX1, LpfMode::Disabled).await?;
+                }
+            }
+            Loopback::BbTxLpfRxLpf | Loopback::BbTxVga1RxLpf => {
+                lms_rxvga1_enable(self, false).await?;
+
+                if !lpf_enabled {
+                    self.set_lpf_mode(BladerfChannel::RX1, LpfMode::Normal).await?;
+                }
+
+                lms_rxvga2_enable(self, true).await?;
+            }
+            Loopback::RfLna1 | Loopback::RfLna2 | Loopback::RfLna3 => {
+                let lna = mode.lna().ok_or(BladerfError::Unexpected)?;
+
+                lms_rxvga1_enable(self, true).await?;
+
+                if !lpf_enabled {
+                    self.set_lpf_mode(BladerfChannel::RX1, LpfMode::Normal).await?;
+                }
+
+                lms_rxvga2_enable(self, true).await?;
+
+                /* The RX PLL output buffer has to feed the LNA in use */
+                lms_modify(self, 0x25, 0x03, lna as u8).await?;
+                lms_select_lna(self, lna).await?;
+            }
+            Loopback::None => {
+                lms_rxvga1_enable(self, true).await?;
+
+                if !lpf_enabled {
+                    self.set_lpf_mode(BladerfChannel::RX1, LpfMode::Normal).await?;
+                }
+
+                lms_rxvga2_enable(self, true).await?;
+
+                let hz = self.get_frequency(BladerfChannel::RX1).await?;
+                self.set_frequency(BladerfChannel::RX1, hz).await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn lms_loopback_path(&self, mode: Loopback) -> Result<()> {
+        let mut loopbben = nios_lms6_read(self, 0x46).await? & !LOOPBBEN_MASK;
+        let mut lben_lbrf = nios_lms6_read(self, 0x08).await? & !(LBEN_MASK | LBRFEN_MASK);
+
+        match mode {
+            Loopback::BbTxLpfRxVga2 => {
+                loopbben |= LOOPBBEN_TXLPF;
+                lben_lbrf |= LBEN_VGA2IN;
+            }
+            Loopback::BbTxVga1RxVga2 => {
+                loopbben |= LOOPBBEN_TXVGA;
+                lben_lbrf |= LBEN_VGA2IN;
+            }
+            Loopback::BbTxLpfRxLpf => {
+                loopbben |= LOOPBBEN_TXLPF;
+                lben_lbrf |= LBEN_LPFIN;
+            }
+            Loopback::BbTxVga1RxLpf => {
+                loopbben |= LOOPBBEN_TXVGA;
+                lben_lbrf |= LBEN_LPFIN;
+            }
+            Loopback::RfLna1 | Loopback::RfLna2 | Loopback::RfLna3 => {
+                lben_lbrf |= mode.lna().ok_or(BladerfError::Unexpected)? as u8;
+            }
+            Loopback::None => {}
+        }
+
+        nios_lms6_write(self, 0x46, loopbben).await?;
+        nios_lms6_write(self, 0x08, lben_lbrf).await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,83 @@
+use crate::lms6::*;
+use anyhow::Result;
+
+/* Supported RF bandwidths, indexed by the 4-bit LPF bandwidth code */
+pub const LMS_BANDWIDTHS: [u32; 16] = [
+    28_000_000, 20_000_000, 14_000_000, 12_000_000, 10_000_000, 8_750_000, 7_000_000, 6_000_000, 5_500_000, 5_000_000, 3_840_000,
+    3_000_000, 2_750_000, 2_500_000, 1_750_000, 1_500_000,
+];
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum LpfMode {
+    Normal,
+    Bypassed,
+    Disabled,
+}
+
+/* Narrowest bandwidth that still covers `hz`, capped at 28 MHz */
+pub fn lms_bandwidth_code(hz: u32) -> u8 {
+    LMS_BANDWIDTHS.iter().rposition(|&bw| bw >= hz).unwrap_or(0) as u8
+}
+
+/* LPF control registers: enable and bandwidth at base, bypass at base + 1 */
+fn lpf_base(dir: BladerfDirection) -> u8 {
+    match dir {
+        BladerfDirection::TX => 0x34,
+        BladerfDirection::RX => 0x54,
+    }
+}
+
+#[cfg(feature = "nusb")]
+impl Device {
+    /* bladeRF 1: enable the channel's LPF and return the bandwidth selected for `hz` */
+    pub async fn set_bandwidth(&self, channel: BladerfChannel, hz: u32) -> Result<u32> {
+        lms_check_board(self)?;
+
+        let base = lpf_base(lms_direction(channel)?);
+        let code = lms_bandwidth_code(hz);
+
+        self.set_lpf_mode(channel, LpfMode::Normal).await?;
+        lms_modify(self, base, 0x3c, code << 2).await?;
+
+        Ok(LMS_BANDWIDTHS[code as usize])
+    }
+
+    pub async fn get_bandwidth(&self, channel: BladerfChannel) -> Result<u32> {
+        lms_check_board(self)?;
+
+        let reg = nios_lms6_read(self, lpf_base(lms_direction(channel)?)).await?;
+
+        Ok(LMS_BANDWIDTHS[((reg >> 2) & 0xf) as usize])
+    }
+
+    pub async fn set_lpf_mode(&self, channel: BladerfChannel, mode: LpfMode) -> Result<()> {
+        lms_check_board(self)?;
+
+        let base = lpf_base(lms_direction(channel)?);
+
+        let (enable, bypass) = match mode {
+            LpfMode::Normal => (1 << 1, 0),
+            LpfMode::Bypassed => (0, 1 << 6),
+            LpfMode::Disabled => (0, 0),
+        };
+
+        lms_modify(self, base, 1 << 1, enable).await?;
+        lms_modify(self, base + 1, 1 << 6, bypass).await
+    }
+
+    pub async fn get_lpf_mode(&self, channel: BladerfChannel) -> Result<LpfMode> {
+        lms_check_board(self)?;
+
+        let base = lpf_base(lms_direction(channel)?);
+
+        let enabled = nios_lms6_read(self, base).await? & (1 << 1) != 0;
+        let bypassed = nios_lms6_read(self, base + 1).await? & (1 << 6) != 0;
+
+        match (enabled, bypassed) {
+            (true, false) => Ok(LpfMode::Normal),
+            (false, true) => Ok(LpfMode::Bypassed),
+            (false, false) => Ok(LpfMode::Disabled),
+            (true, true) => Err(BladerfError::Unexpected.into()),
+        }
+    }
+}
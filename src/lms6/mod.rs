@@ -0,0 +1,60 @@
+use crate::error::BladerfError;
+use crate::nios::nios_access::{nios_lms6_read, nios_lms6_write};
+use crate::{BladerfBoard, BladerfChannel, BladerfDirection, Device};
+use anyhow::Result;
+
+pub mod calibrate;
+pub mod frequency;
+pub mod gain;
+pub mod init;
+pub mod loopback;
+pub mod lpf;
+pub mod path;
+pub mod sampling;
+
+/* Clock enable register: one bit per LMS6002D block that needs the SPI/DSM clock */
+pub const LMS_REG_CLK_EN: u8 = 0x09;
+
+/* Base addresses of the TX and RX PLL register blocks */
+pub(crate) fn lms_pll_base(dir: BladerfDirection) -> u8 {
+    match dir {
+        BladerfDirection::TX => 0x10,
+        BladerfDirection::RX => 0x20,
+    }
+}
+
+/* The LMS6002D is only on the bladeRF 1; the bladeRF 2.0 has an AD9361 instead */
+pub(crate) fn lms_check_board(dev: &Device) -> Result<()> {
+    if dev.board()? != BladerfBoard::BladeRF1 {
+        return Err(BladerfError::Unsupported.into());
+    }
+
+    Ok(())
+}
+
+/* The bladeRF 1 has a single LMS6002D with one RX and one TX channel */
+pub(crate) fn lms_direction(channel: BladerfChannel) -> Result<BladerfDirection> {
+    if channel.index() != 0 {
+        return Err(BladerfError::Inval.into());
+    }
+
+    Ok(channel.direction())
+}
+
+/* Replace the bits of `mask` in an LMS6002D register with those of `value` */
+pub(crate) async fn lms_modify(dev: &Device, addr: u8, mask: u8, value: u8) -> Result<()> {
+    let reg = nios_lms6_read(dev, addr).await?;
+
+    nios_lms6_write(dev, addr, (reg & !mask) | (value & mask)).await?;
+
+    Ok(())
+}
+
+pub(crate) async fn lms_rxvga1_enable(dev: &Device, enable: bool) -> Result<()> {
+    /* 0x7d bit 3 powers RXVGA1 down */
+    lms_modify(dev, 0x7d, 1 << 3, if enable { 0 } else { 1 << 3 }).await
+}
+
+pub(crate) async fn lms_rxvga2_enable(dev: &Device, enable: bool) -> Result<()> {
+    lms_modify(dev, 0x64, 1 << 1, if enable { 1 << 1 } else { 0 }).await
+}
@@ -0,0 +1,160 @@
+use crate::lms6::*;
+use anyhow::Result;
+use core::sync::atomic::Ordering;
+
+/* Marks a direction whose LNA/PA follows the band on every tune */
+pub(crate) const RF_PATH_AUTO: u8 = 0xff;
+
+/* RX low-noise amplifiers; the bladeRF 1 wires LNA1 to the low band and LNA2 to the high band */
+#[repr(u8)]
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum Lna {
+    None = 0,
+    Lna1 = 1,
+    Lna2 = 2,
+    Lna3 = 3,
+}
+
+impl From<u8> for Lna {
+    fn from(value: u8) -> Self {
+        match value & 0x3 {
+            1 => Lna::Lna1,
+            2 => Lna::Lna2,
+            3 => Lna::Lna3,
+            _ => Lna::None,
+        }
+    }
+}
+
+/* TX power amplifier outputs; PA1 drives the low band and PA2 the high band */
+#[repr(u8)]
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum Pa {
+    None = 0,
+    Pa1 = 1,
+    Pa2 = 2,
+    Aux = 3,
+}
+
+impl From<u8> for Pa {
+    fn from(value: u8) -> Self {
+        match value & 0x3 {
+            1 => Pa::Pa1,
+            2 => Pa::Pa2,
+            3 => Pa::Aux,
+            _ => Pa::None,
+        }
+    }
+}
+
+/* PA selection read back from LMS register 0x44 */
+pub fn decode_pa(reg: u8) -> Pa {
+    match (reg >> 3) & 0x3 {
+        1 => Pa::Pa1,
+        2 => Pa::Pa2,
+        _ if reg & (1 << 1) == 0 => Pa::Aux,
+        _ => Pa::None,
+    }
+}
+
+pub(crate) async fn lms_select_lna(dev: &Device, lna: Lna) -> Result<()> {
+    lms_modify(dev, 0x75, 0x30, (lna as u8) << 4).await
+}
+
+pub(crate) async fn lms_select_pa(dev: &Device, pa: Pa) -> Result<()> {
+    /* PA1 and PA2 enables in bits 4:3, AUX PA power down in bit 1 */
+    let reg = match pa {
+        Pa::None => 0x02,
+        Pa::Pa1 => 0x0a,
+        Pa::Pa2 => 0x12,
+        Pa::Aux => 0x00,
+    };
+
+    lms_modify(dev, 0x44, 0x1e, reg).await
+}
+
+#[cfg(feature = "nusb")]
+impl Device {
+    /*
+     * bladeRF 1: select the RX LNA for a custom front end. The choice is pinned:
+     * later tunes apply it instead of the band's LNA until set_rf_path_auto. While
+     * a loopback owns the LNA the choice is only pinned, and applied when the
+     * loopback is turned off.
+     */
+    pub async fn select_lna(&self, lna: Lna) -> Result<()> {
+        lms_check_board(self)?;
+
+        if !self.is_loopback_enabled().await? {
+            lms_select_lna(self, lna).await?;
+        }
+
+        self.rf_path_manual[BladerfDirection::RX as usize].store(lna as u8, Ordering::Relaxed);
+
+        Ok(())
+    }
+
+    pub async fn get_lna(&self) -> Result<Lna> {
+        lms_check_board(self)?;
+
+        Ok(Lna::from(nios_lms6_read(self, 0x75).await? >> 4))
+    }
+
+    /* bladeRF 1: select the TX PA, pinned like select_lna */
+    pub async fn select_pa(&self, pa: Pa) -> Result<()> {
+        lms_check_board(self)?;
+
+        if !self.is_loopback_enabled().await? {
+            lms_select_pa(self, pa).await?;
+        }
+
+        self.rf_path_manual[BladerfDirection::TX as usize].store(pa as u8, Ordering::Relaxed);
+
+        Ok(())
+    }
+
+    pub async fn get_pa(&self) -> Result<Pa> {
+        lms_check_board(self)?;
+
+        Ok(decode_pa(nios_lms6_read(self, 0x44).await?))
+    }
+
+    /* Let tuning pick the band's LNA or PA again; takes effect on the next tune */
+    pub fn set_rf_path_auto(&self, dir: BladerfDirection) {
+        self.rf_path_manual[dir as usize].store(RF_PATH_AUTO, Ordering::Relaxed);
+    }
+
+    pub fn is_rf_path_auto(&self, dir: BladerfDirection) -> bool {
+        self.rf_path_manual[dir as usize].load(Ordering::Relaxed) == RF_PATH_AUTO
+    }
+
+    /* Apply the pinned LNA or PA, or else the one the bladeRF 1 front end expects for the band */
+    pub(crate) async fn lms_select_band_path(&self, dir: BladerfDirection, low_band: bool) -> Result<()> {
+        let pinned = self.rf_path_manual[dir as usize].load(Ordering::Relaxed);
+        let auto = pinned == RF_PATH_AUTO;
+
+        match dir {
+            BladerfDirection::RX => {
+                let lna = if !auto {
+                    Lna::from(pinned)
+                } else if low_band {
+                    Lna::Lna1
+                } else {
+                    Lna::Lna2
+                };
+
+                lms_select_lna(self, lna).await
+            }
+            BladerfDirection::TX => {
+                let pa = if !auto {
+                    Pa::from(pinned)
+                } else if low_band {
+                    Pa::Pa1
+                } else {
+                    Pa::Pa2
+                };
+
+                lms_select_pa(self, pa).await
+            }
+        }
+    }
+}
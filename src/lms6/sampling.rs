@@ -0,0 +1,49 @@
+use crate::error::BladerfError;
+use crate::lms6::*;
+use anyhow::Result;
+
+/* Clock enable register bit 7 connects the external ADC pins to the ADC input */
+const LMS_CLK_EN_EXT_ADC: u8 = 1 << 7;
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum Sampling {
+    /* The ADC samples the RX chain */
+    Internal,
+    /* The ADC samples the external pins (J61 on the bladeRF 1), RXVGA2 powered down */
+    External,
+    /* The registers match neither mode */
+    Unknown,
+}
+
+#[cfg(feature = "nusb")]
+impl Device {
+    pub async fn set_sampling(&self, sampling: Sampling) -> Result<()> {
+        lms_check_board(self)?;
+
+        match sampling {
+            Sampling::Internal => {
+                /* Disconnect the external pins before the RX chain drives the ADC again */
+                lms_modify(self, LMS_REG_CLK_EN, LMS_CLK_EN_EXT_ADC, 0).await?;
+                lms_rxvga2_enable(self, true).await
+            }
+            Sampling::External => {
+                lms_rxvga2_enable(self, false).await?;
+                lms_modify(self, LMS_REG_CLK_EN, LMS_CLK_EN_EXT_ADC, LMS_CLK_EN_EXT_ADC).await
+            }
+            Sampling::Unknown => Err(BladerfError::Inval.into()),
+        }
+    }
+
+    pub async fn get_sampling(&self) -> Result<Sampling> {
+        lms_check_board(self)?;
+
+        let rxvga2 = nios_lms6_read(self, 0x64).await? & (1 << 1) != 0;
+        let external = nios_lms6_read(self, LMS_REG_CLK_EN).await? & LMS_CLK_EN_EXT_ADC != 0;
+
+        Ok(match (rxvga2, external) {
+            (true, false) => Sampling::Internal,
+            (false, true) => Sampling::External,
+            _ => Sampling::Unknown,
+        })
+    }
+}
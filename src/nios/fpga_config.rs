@@ -0,0 +1,154 @@
+use crate::error::BladerfError;
+use crate::nios::nios_access::{nios_config_read, nios_config_write};
+use crate::Device;
+use anyhow::Result;
+
+/* FPGA configuration register (NIOS 8x32 target 1) bits */
+pub const BLADERF_GPIO_LMS_RX_ENABLE: u32 = 1 << 1;
+pub const BLADERF_GPIO_LMS_TX_ENABLE: u32 = 1 << 2;
+pub const BLADERF_GPIO_TX_BAND_SHIFT: u32 = 3;
+pub const BLADERF_GPIO_TX_BAND_MASK: u32 = 0x3 << BLADERF_GPIO_TX_BAND_SHIFT;
+pub const BLADERF_GPIO_RX_BAND_SHIFT: u32 = 5;
+pub const BLADERF_GPIO_RX_BAND_MASK: u32 = 0x3 << BLADERF_GPIO_RX_BAND_SHIFT;
+pub const BLADERF_GPIO_RX_MUX_SHIFT: u32 = 8;
+pub const BLADERF_GPIO_RX_MUX_MASK: u32 = 0x7 << BLADERF_GPIO_RX_MUX_SHIFT;
+pub const BLADERF_GPIO_TIMESTAMP: u32 = 1 << 16;
+pub const BLADERF_GPIO_TIMESTAMP_DIV2: u32 = 1 << 17;
+pub const BLADERF_GPIO_AGC_ENABLE: u32 = 1 << 18;
+pub const BLADERF_GPIO_PACKET: u32 = 1 << 19;
+pub const BLADERF_GPIO_8BIT_MODE: u32 = 1 << 20;
+pub const BLADERF_GPIO_OVERSAMPLE: u32 = 1 << 21;
+
+/* Source of the samples the FPGA sends up the RX path */
+#[repr(u8)]
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Default)]
+pub enum RxMux {
+    #[default]
+    Baseband = 0,
+    Counter12Bit = 1,
+    Counter32Bit = 2,
+    EntryFifo = 3,
+    DigitalLoopback = 4,
+}
+
+impl TryFrom<u32> for RxMux {
+    type Error = anyhow::Error;
+
+    fn try_from(value: u32) -> Result<Self> {
+        match value {
+            0 => Ok(RxMux::Baseband),
+            1 => Ok(RxMux::Counter12Bit),
+            2 => Ok(RxMux::Counter32Bit),
+            3 => Ok(RxMux::EntryFifo),
+            4 => Ok(RxMux::DigitalLoopback),
+            _ => Err(BladerfError::Unexpected.into()),
+        }
+    }
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Default)]
+pub struct FpgaConfig {
+    pub lms_rx_enable: bool,
+    pub lms_tx_enable: bool,
+    /* bladeRF 1 band select: 1 = high band, 2 = low band */
+    pub tx_band: u8,
+    pub rx_band: u8,
+    /* Raw RX mux field, so settings this crate does not know survive a modify; see rx_mux() */
+    pub rx_mux_bits: u8,
+    pub timestamps: bool,
+    pub timestamp_div2: bool,
+    pub agc: bool,
+    pub packet: bool,
+    pub eight_bit: bool,
+    pub oversample: bool,
+    /* Bits without a field of their own, kept as they were read */
+    pub other: u32,
+}
+
+const FPGA_CONFIG_KNOWN_BITS: u32 = BLADERF_GPIO_LMS_RX_ENABLE
+    | BLADERF_GPIO_LMS_TX_ENABLE
+    | BLADERF_GPIO_TX_BAND_MASK
+    | BLADERF_GPIO_RX_BAND_MASK
+    | BLADERF_GPIO_RX_MUX_MASK
+    | BLADERF_GPIO_TIMESTAMP
+    | BLADERF_GPIO_TIMESTAMP_DIV2
+    | BLADERF_GPIO_AGC_ENABLE
+    | BLADERF_GPIO_PACKET
+    | BLADERF_GPIO_8BIT_MODE
+    | BLADERF_GPIO_OVERSAMPLE;
+
+impl FpgaConfig {
+    pub fn rx_mux(&self) -> Result<RxMux> {
+        RxMux::try_from(self.rx_mux_bits as u32)
+    }
+
+    pub fn set_rx_mux(&mut self, mux: RxMux) {
+        self.rx_mux_bits = mux as u8;
+    }
+}
+
+impl From<u32> for FpgaConfig {
+    fn from(reg: u32) -> Self {
+        FpgaConfig {
+            lms_rx_enable: reg & BLADERF_GPIO_LMS_RX_ENABLE != 0,
+            lms_tx_enable: reg & BLADERF_GPIO_LMS_TX_ENABLE != 0,
+            tx_band: ((reg & BLADERF_GPIO_TX_BAND_MASK) >> BLADERF_GPIO_TX_BAND_SHIFT) as u8,
+            rx_band: ((reg & BLADERF_GPIO_RX_BAND_MASK) >> BLADERF_GPIO_RX_BAND_SHIFT) as u8,
+            rx_mux_bits: ((reg & BLADERF_GPIO_RX_MUX_MASK) >> BLADERF_GPIO_RX_MUX_SHIFT) as u8,
+            timestamps: reg & BLADERF_GPIO_TIMESTAMP != 0,
+            timestamp_div2: reg & BLADERF_GPIO_TIMESTAMP_DIV2 != 0,
+            agc: reg & BLADERF_GPIO_AGC_ENABLE != 0,
+            packet: reg & BLADERF_GPIO_PACKET != 0,
+            eight_bit: reg & BLADERF_GPIO_8BIT_MODE != 0,
+            oversample: reg & BLADERF_GPIO_OVERSAMPLE != 0,
+            other: reg & !FPGA_CONFIG_KNOWN_BITS,
+        }
+    }
+}
+
+impl From<FpgaConfig> for u32 {
+    fn from(config: FpgaConfig) -> u32 {
+        let flag = |set: bool, bit: u32| if set { bit } else { 0 };
+
+        flag(config.lms_rx_enable, BLADERF_GPIO_LMS_RX_ENABLE)
+            | flag(config.lms_tx_enable, BLADERF_GPIO_LMS_TX_ENABLE)
+            | (((config.tx_band as u32) << BLADERF_GPIO_TX_BAND_SHIFT) & BLADERF_GPIO_TX_BAND_MASK)
+            | (((config.rx_band as u32) << BLADERF_GPIO_RX_BAND_SHIFT) & BLADERF_GPIO_RX_BAND_MASK)
+            | (((config.rx_mux_bits as u32) << BLADERF_GPIO_RX_MUX_SHIFT) & BLADERF_GPIO_RX_MUX_MASK)
+            | flag(config.timestamps, BLADERF_GPIO_TIMESTAMP)
+            | flag(config.timestamp_div2, BLADERF_GPIO_TIMESTAMP_DIV2)
+            | flag(config.agc, BLADERF_GPIO_AGC_ENABLE)
+            | flag(config.packet, BLADERF_GPIO_PACKET)
+            | flag(config.eight_bit, BLADERF_GPIO_8BIT_MODE)
+            | flag(config.oversample, BLADERF_GPIO_OVERSAMPLE)
+            | (config.other & !FPGA_CONFIG_KNOWN_BITS)
+    }
+}
+
+pub async fn fpga_config_read(dev: &Device) -> Result<FpgaConfig> {
+    let reg = nios_config_read(dev).await?;
+
+    Ok(FpgaConfig::from(reg))
+}
+
+pub async fn fpga_config_write(dev: &Device, config: FpgaConfig) -> Result<()> {
+    nios_config_write(dev, config.into()).await?;
+
+    Ok(())
+}
+
+/*
+ * Read the register, apply `f` and write the result back. The device's config lock
+ * is held throughout, so concurrent modifications through this crate never clobber
+ * each other's fields.
+ */
+#[cfg(feature = "std")]
+pub async fn fpga_config_modify<F: FnOnce(&mut FpgaConfig)>(dev: &Device, f: F) -> Result<FpgaConfig> {
+    let _guard = dev.config_lock.lock().await;
+
+    let mut config = fpga_config_read(dev).await?;
+    f(&mut config);
+    fpga_config_write(dev, config).await?;
+
+    Ok(config)
+}
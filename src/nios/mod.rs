@@ -2,5 +2,7 @@ use crate::usb::{bulk_transfer_in, bulk_transfer_out};
 use crate::{BladerfDirection, BladerfVersion, Device};
 use anyhow::Result;
 
-mod packet;
-pub mod nios_access;
\ No newline at end of file
+pub(crate) mod packet;
+pub mod nios_access;
+pub mod fpga_config;
+pub mod rffe_control;
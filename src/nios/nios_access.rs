@@ -1,4 +1,7 @@
+use crate::error::BladerfError;
 use crate::nios::*;
+use crate::retune::RetuneResponse;
+use crate::trigger::TriggerSignal;
 use crate::usb::{bulk_transfer_in, bulk_transfer_out};
 use crate::{BladerfDirection, BladerfVersion, Device};
 use anyhow::{Error, Result};
@@ -8,6 +11,10 @@ pub async fn nios_access(
     dev: &Device,
     buf: &[u8; 16],
 ) -> Result<[u8; 16]> {
+    /* Another task's request must not slip in before this response is read */
+    #[cfg(feature = "std")]
+    let _guard = dev.nios_lock.lock().await;
+
     /* Send the command */
     bulk_transfer_out::<0x02>(dev, buf).await?;
 
@@ -23,9 +30,9 @@ pub async fn nios_8x8_read(
 ) -> Result<u8> {
     let buf = pkt_8x8::pack_8x8(id, false, addr, 0);
 
-    nios_access(dev, &buf).await?;
+    let resp = nios_access(dev, &buf).await?;
 
-    let out = pkt_8x8::unpack_8x8(&buf)?.3;
+    let out = pkt_8x8::unpack_8x8(&resp)?.3;
 
     Ok(out)
 }
@@ -38,9 +45,9 @@ pub async fn nios_8x8_write(
 ) -> Result<u8> {
     let buf = pkt_8x8::pack_8x8(id, true, addr, data);
 
-    nios_access(dev, &buf).await?;
+    let resp = nios_access(dev, &buf).await?;
 
-    let out = pkt_8x8::unpack_8x8(&buf)?.3;
+    let out = pkt_8x8::unpack_8x8(&resp)?.3;
 
     Ok(out)
 }
@@ -52,9 +59,9 @@ pub async fn nios_8x16_read(
 ) -> Result<u16> {
     let buf = pkt_8x16::pack_8x16(id, false, addr, 0);
 
-    nios_access(dev, &buf).await?;
+    let resp = nios_access(dev, &buf).await?;
 
-    let out = pkt_8x16::unpack_8x16(&buf)?.3;
+    let out = pkt_8x16::unpack_8x16(&resp)?.3;
 
     Ok(out)
 }
@@ -67,9 +74,9 @@ pub async fn nios_8x16_write(
 ) -> Result<u16> {
     let buf = pkt_8x16::pack_8x16(id, true, addr, data);
 
-    nios_access(dev, &buf).await?;
+    let resp = nios_access(dev, &buf).await?;
 
-    let out = pkt_8x16::unpack_8x16(&buf)?.3;
+    let out = pkt_8x16::unpack_8x16(&resp)?.3;
 
     Ok(out)
 }
@@ -81,9 +88,9 @@ pub async fn nios_8x32_read(
 ) -> Result<u32> {
     let buf = pkt_8x32::pack_8x32(id, false, addr, 0);
 
-    nios_access(dev, &buf).await?;
+    let resp = nios_access(dev, &buf).await?;
 
-    let out = pkt_8x32::unpack_8x32(&buf)?.3;
+    let out = pkt_8x32::unpack_8x32(&resp)?.3;
 
     Ok(out)
 }
@@ -96,9 +103,9 @@ pub async fn nios_8x32_write(
 ) -> Result<u32> {
     let buf = pkt_8x32::pack_8x32(id, true, addr, data);
 
-    nios_access(dev, &buf).await?;
+    let resp = nios_access(dev, &buf).await?;
 
-    let out = pkt_8x32::unpack_8x32(&buf)?.3;
+    let out = pkt_8x32::unpack_8x32(&resp)?.3;
 
     Ok(out)
 }
@@ -110,9 +117,9 @@ pub async fn nios_16x64_read(
 ) -> Result<u64> {
     let buf = pkt_16x64::pack_16x64(id, false, addr, 0);
 
-    nios_access(dev, &buf).await?;
+    let resp = nios_access(dev, &buf).await?;
 
-    let out = pkt_16x64::unpack_16x64(&buf)?.3;
+    let out = pkt_16x64::unpack_16x64(&resp)?.3;
 
     Ok(out)
 }
@@ -125,9 +132,9 @@ pub async fn nios_16x64_write(
 ) -> Result<u64> {
     let buf = pkt_16x64::pack_16x64(id, true, addr, data);
 
-    nios_access(dev, &buf).await?;
+    let resp = nios_access(dev, &buf).await?;
 
-    let out = pkt_16x64::unpack_16x64(&buf)?.3;
+    let out = pkt_16x64::unpack_16x64(&resp)?.3;
 
     Ok(out)
 }
@@ -139,9 +146,9 @@ pub async fn nios_32x32_read(
 ) -> Result<u32> {
     let buf = pkt_32x32::pack_32x32(id, false, addr, 0);
 
-    nios_access(dev, &buf).await?;
+    let resp = nios_access(dev, &buf).await?;
 
-    let out = pkt_32x32::unpack_32x32(&buf)?.3;
+    let out = pkt_32x32::unpack_32x32(&resp)?.3;
 
     Ok(out)
 }
@@ -154,9 +161,9 @@ pub async fn nios_32x32_write(
 ) -> Result<u32> {
     let buf = pkt_32x32::pack_32x32(id, true, addr, data);
 
-    nios_access(dev, &buf).await?;
+    let resp = nios_access(dev, &buf).await?;
 
-    let out = pkt_32x32::unpack_32x32(&buf)?.3;
+    let out = pkt_32x32::unpack_32x32(&resp)?.3;
 
     Ok(out)
 }
@@ -164,9 +171,9 @@ pub async fn nios_32x32_write(
 pub async fn nios_32x32_masked_read(dev: &Device, id: u8, mask: u32) -> Result<u32> {
     let buf = pkt_32x32::pack_32x32(id, false, mask, 0);
 
-    nios_access(dev, &buf).await?;
+    let resp = nios_access(dev, &buf).await?;
 
-    let out = pkt_32x32::unpack_32x32(&buf)?.3;
+    let out = pkt_32x32::unpack_32x32(&resp)?.3;
 
     Ok(out)
 }
@@ -174,14 +181,14 @@ pub async fn nios_32x32_masked_read(dev: &Device, id: u8, mask: u32) -> Result<u
 pub async fn nios_32x32_masked_write(dev: &Device, id: u8, mask: u32, val: u32) -> Result<u32> {
     let buf = pkt_32x32::pack_32x32(id, true, mask, val);
 
-    nios_access(dev, &buf).await?;
+    let resp = nios_access(dev, &buf).await?;
 
-    let out = pkt_32x32::unpack_32x32(&buf)?.3;
+    let out = pkt_32x32::unpack_32x32(&resp)?.3;
 
     Ok(out)
 }
 
-pub async fn nios_config_read<const endpoint: u8>(
+pub async fn nios_config_read(
     dev: &Device,
 ) -> Result<u32> {
     let out = nios_8x32_read(dev, 1, 0).await?;
@@ -223,9 +230,9 @@ pub async fn nios_get_timestamp(
 
     let buf = pkt_8x64::pack_8x64(0, false, addr, 0);
 
-    nios_access(dev, &buf).await?;
+    let resp = nios_access(dev, &buf).await?;
 
-    let timestamp = pkt_8x64::unpack_8x64(&buf)?.3;
+    let timestamp = pkt_8x64::unpack_8x64(&resp)?.3;
 
     Ok(timestamp)
 }
@@ -494,76 +501,81 @@ pub async fn nios_expansion_gpio_dir_write(dev: &Device, mask: u32, val: u32) ->
     Ok(out)
 }
 
-pub async fn nios_retune(dev: &Device, ch: u8, timestamp: u64, nint: u16, nfrac: u32, freqsel: u8, vcocap: u8, low_band: bool, xb_gpio: u8, quick_tune: bool) -> Result<u64> {
+pub async fn nios_retune(dev: &Device, ch: u8, timestamp: u64, nint: u16, nfrac: u32, freqsel: u8, vcocap: u8, low_band: bool, xb_gpio: u8, quick_tune: bool) -> Result<RetuneResponse> {
     let buf = pkt_retune::pack_retune(
         ch, timestamp, nint, nfrac, freqsel, vcocap, low_band, xb_gpio, quick_tune,
     );
 
-    nios_access(dev, &buf).await?;
+    let resp = nios_access(dev, &buf).await?;
 
-    let out = pkt_retune::unpack_retune(&buf);
+    let out = pkt_retune::unpack_retune(&resp);
 
-    if out.2 & 0b00000010 == 0 {
-        let err = if out.0 == 0 {
-            Error::msg("Error -1: FPGA tuning reported failure.")
-        } else {
-            Error::msg("Error -15: BladeRF retune queue is full. Try again later.")
-        };
-        return Err(err);
+    if out.2 & pkt_retune::NIOS_PKT_RETUNERESP_FLAG_SUCCESS == 0 {
+        return Err(retune_failure(timestamp));
     }
 
-    Ok(out.0)
+    Ok(RetuneResponse::from_flags(out.0, out.2))
 }
 
-pub async fn nios_retune2(dev: &Device, ch: u8, timestamp: u64, nios_profile: u16, rffe_profile: u8, port: u8, spdt: u8) -> Result<u64> {
+pub async fn nios_retune2(dev: &Device, ch: u8, timestamp: u64, nios_profile: u16, rffe_profile: u8, port: u8, spdt: u8) -> Result<RetuneResponse> {
     let buf = pkt_retune::pack_retune2(ch, timestamp, nios_profile, rffe_profile, port, spdt);
 
-    nios_access(dev, &buf).await?;
+    let resp = nios_access(dev, &buf).await?;
+
+    let out = pkt_retune::unpack_retune2(&resp);
+
+    if out.1 & pkt_retune::NIOS_PKT_RETUNERESP_FLAG_SUCCESS == 0 {
+        return Err(retune_failure(timestamp));
+    }
 
-    let out = pkt_retune::unpack_retune2(&buf);
+    Ok(RetuneResponse::from_flags(out.0, out.1))
+}
 
-    if out.1 & 0b00000010 == 0 {
-        let err = if out.0 == 0 {
-            Error::msg("Error -1: FPGA tuning reported failure.")
-        } else {
-            Error::msg("Error -15: BladeRF retune queue is full. Try again later.")
-        };
-        return Err(err);
+/* An immediate retune can only fail in the tuning itself; a scheduled one is refused when the queue is full */
+pub fn retune_failure(timestamp: u64) -> Error {
+    if timestamp == pkt_retune::NIOS_PKT_RETUNE_NOW {
+        BladerfError::Unexpected.into()
+    } else {
+        BladerfError::QueueFull.into()
     }
-    Ok(out.0)
 }
 
-pub async fn nios_read_trigger(dev: &Device, ch: BladerfDirection, trigger: u8) -> Result<u8> {
+/*
+ * NIOS 8x8 request for a trigger control register. As in libbladerf, only the one
+ * external trigger line is implemented; it sits at address 0 of the direction's
+ * target and the user signals are refused.
+ */
+pub fn pack_trigger_request(ch: BladerfDirection, trigger: TriggerSignal, write: bool, value: u8) -> Result<[u8; 16]> {
     let nios_id: u8 = match ch {
         BladerfDirection::TX => 3,
 
         BladerfDirection::RX => 4,
     };
 
-    /* Only 1 external trigger is currently supported */
-    match trigger {
-        0..=2 => {}
+    let addr = match trigger {
+        TriggerSignal::J71_4 | TriggerSignal::J51_1 | TriggerSignal::MiniExp1 => 0,
+        _ => return Err(BladerfError::Inval.into()),
+    };
 
-        _ => return Err(Error::msg("Error -1: Invalid Trigger.")),
-    }
+    Ok(pkt_8x8::pack_8x8(nios_id, write, addr, value))
+}
 
-    let out = nios_8x8_read(dev, nios_id, 0).await?;
+pub async fn nios_read_trigger(dev: &Device, ch: BladerfDirection, trigger: TriggerSignal) -> Result<u8> {
+    let buf = pack_trigger_request(ch, trigger, false, 0)?;
+
+    let resp = nios_access(dev, &buf).await?;
+
+    let out = pkt_8x8::unpack_8x8(&resp)?.3;
 
     Ok(out)
 }
 
-pub async fn nios_write_trigger(dev: &Device, ch: BladerfDirection, trigger: u8, value: u8) -> Result<u8> {
-    let nios_id: u8 = match ch {
-        BladerfDirection::TX => 3,
-
-        BladerfDirection::RX => 4,
-    };
+pub async fn nios_write_trigger(dev: &Device, ch: BladerfDirection, trigger: TriggerSignal, value: u8) -> Result<u8> {
+    let buf = pack_trigger_request(ch, trigger, true, value)?;
 
-    if trigger > 2 {
-        return Err(Error::msg("Error -1: Invalid Trigger."));
-    }
+    let resp = nios_access(dev, &buf).await?;
 
-    let out = nios_8x8_write(dev, nios_id, 0, value).await?;
+    let out = pkt_8x8::unpack_8x8(&resp)?.3;
 
     Ok(out)
 }
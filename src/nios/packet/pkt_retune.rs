@@ -6,6 +6,14 @@ const NIOS_PKT_RETUNE_IDX_BANDSEL: usize = 14;
 const NIOS_PKT_RETUNE_IDX_RESV: usize = 15;
 const NIOS_PKT_RETUNE_MAGIC: u8 = 'T' as u8;
 
+/* Special timestamps: tune immediately, or flush every scheduled retune */
+pub const NIOS_PKT_RETUNE_NOW: u64 = 0;
+pub const NIOS_PKT_RETUNE_CLEAR_QUEUE: u64 = u64::MAX;
+
+/* The retune was carried out and the duration reported is valid */
+pub const NIOS_PKT_RETUNERESP_FLAG_TSVTUNE_VALID: u8 = 1 << 0;
+pub const NIOS_PKT_RETUNERESP_FLAG_SUCCESS: u8 = 1 << 1;
+
 const NIOS_PKT_RETUNERESP_IDX_VCOCAP: usize = 9;
 const NIOS_PKT_RETUNERESP_IDX_FLAGS: usize = 10;
 
@@ -105,10 +113,8 @@ pub fn pack_retune2(
     pkt_port = port & (!retune_mask);
 
     /* Set the IS_RX bit (if needed) */
-    if (pkt_port != 0) | (module == 1) {
-        pkt_port = 0;
-    } else {
-        pkt_port = retune_mask;
+    if module == 0 {
+        pkt_port |= retune_mask;
     }
 
     buf[NIOS_PKT_RETUNE2_IDX_MAGIC] = NIOS_PKT_RETUNE2_MAGIC;
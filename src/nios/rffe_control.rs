@@ -0,0 +1,18 @@
+/* bladeRF 2.0 RFFE control register (NIOS 8x32 target 3) bits */
+pub const RFFE_CONTROL_RESET_N: u32 = 0;
+pub const RFFE_CONTROL_ENABLE: u32 = 1;
+pub const RFFE_CONTROL_TXNRX: u32 = 2;
+pub const RFFE_CONTROL_EN_AGC: u32 = 3;
+pub const RFFE_CONTROL_SYNC_IN: u32 = 4;
+pub const RFFE_CONTROL_RX_BIAS_EN: u32 = 5;
+pub const RFFE_CONTROL_RX_SPDT_1: u32 = 6;
+pub const RFFE_CONTROL_RX_SPDT_2: u32 = 8;
+pub const RFFE_CONTROL_TX_BIAS_EN: u32 = 10;
+pub const RFFE_CONTROL_TX_SPDT_1: u32 = 11;
+pub const RFFE_CONTROL_TX_SPDT_2: u32 = 13;
+pub const RFFE_CONTROL_MIMO_RX_EN_0: u32 = 15;
+pub const RFFE_CONTROL_MIMO_TX_EN_0: u32 = 16;
+pub const RFFE_CONTROL_MIMO_RX_EN_1: u32 = 17;
+pub const RFFE_CONTROL_MIMO_TX_EN_1: u32 = 18;
+pub const RFFE_CONTROL_ADF_MUXOUT: u32 = 19;
+pub const RFFE_CONTROL_CTRL_OUT: u32 = 24;
@@ -0,0 +1,160 @@
+use crate::error::BladerfError;
+use crate::nios::packet::pkt_retune::NIOS_PKT_RETUNE_NOW;
+use crate::retune::{QuickTune, RetuneTransport, RETUNE_QUEUE_DEPTH};
+use crate::BladerfDirection;
+use alloc::vec::Vec;
+use anyhow::Result;
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub struct Hop {
+    pub frequency: u64,
+    pub tune: QuickTune,
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum HopOutcome {
+    /* Queued on the FPGA ahead of its start */
+    Scheduled,
+    /*
+     * Its start had passed, either before it was sent or by the time the FPGA got
+     * it, so it was retuned immediately; `duration` is the tuning time reported
+     */
+    Late { duration: u64 },
+    /* This hop and the `count - 1` after it had their whole dwell pass before they could be queued, so they were skipped */
+    Missed { count: u64 },
+    /* The FPGA refused the retune, although the queue had room for a scheduled one */
+    Failed,
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub struct HopReport {
+    /* Position in the hop sequence since the start, counting repeats */
+    pub index: u64,
+    pub frequency: u64,
+    pub timestamp: u64,
+    pub outcome: HopOutcome,
+}
+
+/*
+ * Frequency hopping on timed retunes. Hop `n` starts at `start + n * dwell` and
+ * the sequence repeats. Call `pump` regularly: it tops the device retune queue
+ * back up to RETUNE_QUEUE_DEPTH, going by the retunes the transport reports as
+ * still pending, so retunes scheduled outside the scheduler count against it too.
+ */
+pub struct HopScheduler<'a, T: RetuneTransport> {
+    transport: &'a T,
+    direction: BladerfDirection,
+    hops: Vec<Hop>,
+    dwell: u64,
+    start: u64,
+    next: u64,
+}
+
+impl<'a, T: RetuneTransport> HopScheduler<'a, T> {
+    /* `tune` computes the quick-tune parameters for a frequency; each distinct frequency is computed once */
+    pub fn new<F: FnMut(u64) -> Result<QuickTune>>(
+        transport: &'a T,
+        direction: BladerfDirection,
+        frequencies: &[u64],
+        dwell: u64,
+        start: u64,
+        mut tune: F,
+    ) -> Result<Self> {
+        if frequencies.is_empty() || dwell == 0 {
+            return Err(BladerfError::Inval.into());
+        }
+
+        let mut hops: Vec<Hop> = Vec::with_capacity(frequencies.len());
+
+        for &frequency in frequencies {
+            let tune = match hops.iter().find(|hop| hop.frequency == frequency) {
+                Some(hop) => hop.tune,
+                None => tune(frequency)?,
+            };
+
+            hops.push(Hop { frequency, tune });
+        }
+
+        Ok(HopScheduler {
+            transport,
+            direction,
+            hops,
+            dwell,
+            start,
+            next: 0,
+        })
+    }
+
+    pub fn hops(&self) -> &[Hop] {
+        &self.hops
+    }
+
+    /* Index of the next hop to be queued */
+    pub fn next_index(&self) -> u64 {
+        self.next
+    }
+
+    pub fn hop_timestamp(&self, index: u64) -> u64 {
+        self.start + index * self.dwell
+    }
+
+    fn hop(&self, index: u64) -> Hop {
+        self.hops[(index % self.hops.len() as u64) as usize]
+    }
+
+    pub async fn pump(&mut self) -> Result<Vec<HopReport>> {
+        let now = self.transport.get_timestamp(self.direction).await?;
+        let mut reports = Vec::new();
+
+        /* Jump straight to the hop the device clock is in, however far behind we are */
+        let current = now.saturating_sub(self.start) / self.dwell;
+
+        if now >= self.start && current > self.next {
+            reports.push(HopReport {
+                index: self.next,
+                frequency: self.hop(self.next).frequency,
+                timestamp: self.hop_timestamp(self.next),
+                outcome: HopOutcome::Missed { count: current - self.next },
+            });
+            self.next = current;
+        }
+
+        let mut pending = self.transport.pending_retunes(self.direction).await?;
+
+        while pending < RETUNE_QUEUE_DEPTH {
+            let index = self.next;
+            let timestamp = self.hop_timestamp(index);
+            let hop = self.hop(index);
+
+            let outcome = if timestamp <= now {
+                match self.transport.retune(self.direction, NIOS_PKT_RETUNE_NOW, &hop.tune).await {
+                    Ok(response) => HopOutcome::Late { duration: response.duration },
+                    Err(e) if e.downcast_ref::<BladerfError>() == Some(&BladerfError::Unexpected) => HopOutcome::Failed,
+                    Err(e) => return Err(e),
+                }
+            } else {
+                match self.transport.retune(self.direction, timestamp, &hop.tune).await {
+                    /* The device clock passed the start before the request arrived */
+                    Ok(response) if response.tuned => HopOutcome::Late { duration: response.duration },
+                    Ok(_) => {
+                        pending += 1;
+                        HopOutcome::Scheduled
+                    }
+                    /* The queue had room, so the FPGA refused this retune itself */
+                    Err(e) if e.downcast_ref::<BladerfError>() == Some(&BladerfError::QueueFull) => HopOutcome::Failed,
+                    Err(e) => return Err(e),
+                }
+            };
+
+            reports.push(HopReport {
+                index,
+                frequency: hop.frequency,
+                timestamp,
+                outcome,
+            });
+            self.next += 1;
+        }
+
+        Ok(reports)
+    }
+}
@@ -0,0 +1,145 @@
+#[cfg(feature = "std")]
+pub mod hopping;
+#[cfg(feature = "std")]
+pub mod queue;
+#[cfg(feature = "std")]
+pub mod quick_tune;
+
+use crate::nios::nios_access::{nios_get_timestamp, nios_retune, nios_retune2};
+use crate::nios::packet::pkt_retune::{NIOS_PKT_RETUNERESP_FLAG_TSVTUNE_VALID, NIOS_PKT_RETUNE_CLEAR_QUEUE, NIOS_PKT_RETUNE_NOW};
+use crate::{BladerfBoard, BladerfDirection, Device};
+use anyhow::Result;
+use core::future::Future;
+
+/* Retune requests the NIOS firmware holds before reporting the queue as full */
+pub const RETUNE_QUEUE_DEPTH: usize = 16;
+
+/* Synthesizer state that lets the FPGA retune without searching for it again */
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum QuickTune {
+    /* LMS6002D PLL settings and the XB-200 GPIO state */
+    BladeRF1 {
+        nint: u16,
+        nfrac: u32,
+        freqsel: u8,
+        vcocap: u8,
+        low_band: bool,
+        xb_gpio: u8,
+    },
+    /* AD9361 fastlock profile and the matching RF front end switch settings */
+    BladeRF2 {
+        nios_profile: u16,
+        rffe_profile: u8,
+        port: u8,
+        spdt: u8,
+    },
+}
+
+/* What the NIOS reports for a retune it accepted */
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Default)]
+pub struct RetuneResponse {
+    /* Ticks the tuning took, valid only when `tuned` */
+    pub duration: u64,
+    /* The retune was carried out before the response was sent, rather than queued */
+    pub tuned: bool,
+}
+
+impl RetuneResponse {
+    pub fn from_flags(duration: u64, flags: u8) -> Self {
+        RetuneResponse {
+            duration,
+            tuned: flags & NIOS_PKT_RETUNERESP_FLAG_TSVTUNE_VALID != 0,
+        }
+    }
+}
+
+pub trait RetuneTransport {
+    /* Retune at `timestamp`, or immediately for NIOS_PKT_RETUNE_NOW */
+    fn retune(&self, dir: BladerfDirection, timestamp: u64, tune: &QuickTune) -> impl Future<Output = Result<RetuneResponse>>;
+
+    fn get_timestamp(&self, dir: BladerfDirection) -> impl Future<Output = Result<u64>>;
+
+    /* Scheduled retunes still waiting in the device queue, see Device::pending_retunes() */
+    #[cfg(feature = "std")]
+    fn pending_retunes(&self, dir: BladerfDirection) -> impl Future<Output = Result<usize>>;
+}
+
+#[cfg(feature = "nusb")]
+impl RetuneTransport for Device {
+    async fn retune(&self, dir: BladerfDirection, timestamp: u64, tune: &QuickTune) -> Result<RetuneResponse> {
+        let response = match *tune {
+            QuickTune::BladeRF1 {
+                nint,
+                nfrac,
+                freqsel,
+                vcocap,
+                low_band,
+                xb_gpio,
+            } => nios_retune(self, dir as u8, timestamp, nint, nfrac, freqsel, vcocap, low_band, xb_gpio, true).await,
+            QuickTune::BladeRF2 {
+                nios_profile,
+                rffe_profile,
+                port,
+                spdt,
+            } => nios_retune2(self, dir as u8, timestamp, nios_profile, rffe_profile, port, spdt).await,
+        }?;
+
+        #[cfg(feature = "std")]
+        if timestamp != NIOS_PKT_RETUNE_NOW && !response.tuned {
+            self.retune_queues[dir as usize].lock().await.push(timestamp);
+        }
+
+        Ok(response)
+    }
+
+    async fn get_timestamp(&self, dir: BladerfDirection) -> Result<u64> {
+        nios_get_timestamp(self, dir).await
+    }
+
+    #[cfg(feature = "std")]
+    async fn pending_retunes(&self, dir: BladerfDirection) -> Result<usize> {
+        Device::pending_retunes(self, dir).await
+    }
+}
+
+#[cfg(feature = "nusb")]
+impl Device {
+    /*
+     * Drop every retune scheduled for one direction. Retunes already carried out
+     * are not undone. A scheduled retune refused with BladerfError::QueueFull can
+     * be retried after cancelling, or once earlier entries have been consumed.
+     */
+    pub async fn cancel_scheduled_retunes(&self, direction: BladerfDirection) -> Result<()> {
+        let module = direction as u8;
+
+        match self.board()? {
+            BladerfBoard::BladeRF1 => {
+                nios_retune(self, module, NIOS_PKT_RETUNE_CLEAR_QUEUE, 0, 0, 0, 0, false, 0, false).await?;
+            }
+            BladerfBoard::BladeRF2 => {
+                nios_retune2(self, module, NIOS_PKT_RETUNE_CLEAR_QUEUE, 0, 0, 0, 0).await?;
+            }
+        }
+
+        #[cfg(feature = "std")]
+        self.retune_queues[direction as usize].lock().await.clear();
+
+        Ok(())
+    }
+
+    /*
+     * Retunes scheduled for one direction that the device clock has not reached
+     * yet, out of RETUNE_QUEUE_DEPTH. Only retunes queued through RetuneTransport
+     * on this Device are counted.
+     */
+    #[cfg(feature = "std")]
+    pub async fn pending_retunes(&self, direction: BladerfDirection) -> Result<usize> {
+        let now = nios_get_timestamp(self, direction).await?;
+        let mut queue = self.retune_queues[direction as usize].lock().await;
+
+        queue.expire(now);
+
+        Ok(queue.pending())
+    }
+}
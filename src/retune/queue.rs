@@ -0,0 +1,35 @@
+use crate::retune::RETUNE_QUEUE_DEPTH;
+use alloc::collections::VecDeque;
+
+/*
+ * Host-side view of one direction's retune queue. The NIOS firmware does not
+ * report how many retunes it holds, so this tracks the timestamps of those
+ * scheduled through this host and drops them once the device clock passes them.
+ */
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct RetuneQueue {
+    pending: VecDeque<u64>,
+}
+
+impl RetuneQueue {
+    pub fn push(&mut self, timestamp: u64) {
+        self.pending.push_back(timestamp);
+    }
+
+    /* Forget the retunes the firmware has carried out by device time `now` */
+    pub fn expire(&mut self, now: u64) {
+        self.pending.retain(|&timestamp| timestamp > now);
+    }
+
+    pub fn clear(&mut self) {
+        self.pending.clear();
+    }
+
+    pub fn pending(&self) -> usize {
+        self.pending.len()
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.pending() >= RETUNE_QUEUE_DEPTH
+    }
+}
@@ -0,0 +1,138 @@
+use crate::error::BladerfError;
+use crate::lms6::frequency::LmsFreq;
+use crate::lms6::lms_direction;
+use crate::nios::nios_access::{nios_expansion_gpio_read, nios_rffe_control_read, nios_rffe_fastlock_save};
+use crate::nios::packet::pkt_retune::NIOS_PKT_RETUNE_NOW;
+use crate::nios::rffe_control::{RFFE_CONTROL_RX_SPDT_1, RFFE_CONTROL_TX_SPDT_1};
+use crate::retune::{QuickTune, RetuneTransport};
+use crate::rfic::{rfic_read, rfic_write, RficCommand};
+use crate::{BladerfBoard, BladerfChannel, BladerfDirection, Device};
+use anyhow::Result;
+use core::sync::atomic::Ordering;
+
+/* Profile slots in NIOS memory, and fastlock profiles inside the AD9361 */
+pub const NIOS_PROFILE_MAX: u16 = 256;
+pub const RFFE_PROFILE_MAX: u16 = 8;
+
+/* bladeRF 2.0 band split: port B/TXB below it, port A/TXA above it */
+const BLADERF2_BAND_SPLIT: u64 = 3_000_000_000;
+
+/* XB-200 expansion GPIO bits, as libbladerf names them */
+pub const BLADERF_XB_CONFIG_TX_BYPASS_MASK: u32 = 0x0c;
+pub const BLADERF_XB_CONFIG_RX_BYPASS_MASK: u32 = 0x30;
+pub const BLADERF_XB_TX_ENABLE: u32 = 0x1000;
+pub const BLADERF_XB_RX_ENABLE: u32 = 0x2000;
+pub const BLADERF_XB_TX_MASK: u32 = 0x0c00_0000;
+pub const BLADERF_XB_TX_SHIFT: u32 = 26;
+pub const BLADERF_XB_RX_MASK: u32 = 0x3000_0000;
+pub const BLADERF_XB_RX_SHIFT: u32 = 28;
+
+/* QuickTune::BladeRF1 xb_gpio layout, which the NIOS applies on retune */
+pub const LMS_FREQ_XB_200_ENABLE: u8 = 1 << 7;
+pub const LMS_FREQ_XB_200_MODULE_RX: u8 = 1 << 6;
+pub const LMS_FREQ_XB_200_FILTER_SW_SHIFT: u8 = 4;
+pub const LMS_FREQ_XB_200_PATH_SHIFT: u8 = 2;
+
+/*
+ * XB-200 filter bank switch and path of one direction, taken from the expansion
+ * GPIO. Zero when the XB-200 is not enabled for that direction, so retunes leave
+ * the expansion header alone.
+ */
+pub fn xb200_quick_tune_gpio(gpio: u32, dir: BladerfDirection) -> u8 {
+    let (enable, filter, path) = match dir {
+        BladerfDirection::RX => (
+            BLADERF_XB_RX_ENABLE,
+            (gpio & BLADERF_XB_RX_MASK) >> BLADERF_XB_RX_SHIFT,
+            (gpio & BLADERF_XB_CONFIG_RX_BYPASS_MASK) >> 4,
+        ),
+        BladerfDirection::TX => (
+            BLADERF_XB_TX_ENABLE,
+            (gpio & BLADERF_XB_TX_MASK) >> BLADERF_XB_TX_SHIFT,
+            (gpio & BLADERF_XB_CONFIG_TX_BYPASS_MASK) >> 2,
+        ),
+    };
+
+    if gpio & enable == 0 {
+        return 0;
+    }
+
+    let module = if dir == BladerfDirection::RX { LMS_FREQ_XB_200_MODULE_RX } else { 0 };
+
+    LMS_FREQ_XB_200_ENABLE | module | ((filter as u8) << LMS_FREQ_XB_200_FILTER_SW_SHIFT) | ((path as u8) << LMS_FREQ_XB_200_PATH_SHIFT)
+}
+
+impl From<LmsFreq> for QuickTune {
+    fn from(freq: LmsFreq) -> Self {
+        QuickTune::BladeRF1 {
+            nint: freq.nint,
+            nfrac: freq.nfrac,
+            freqsel: freq.freqsel,
+            vcocap: freq.vcocap,
+            low_band: freq.low_band,
+            xb_gpio: 0,
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl Device {
+    /*
+     * Capture the channel's current tuning so it can be replayed later without
+     * a VCO search. On the bladeRF 1 the XB-200 filter and path are captured when
+     * it is enabled. On the bladeRF 2.0 the AD9361 state is saved into the next
+     * of NIOS_PROFILE_MAX NIOS profiles per direction, from which each retune
+     * loads it back into one of the RFFE_PROFILE_MAX AD9361 slots, so every
+     * capture stays valid. Profiles are never reused: once a direction has used
+     * them all, this fails with BladerfError::QueueFull until the device is
+     * listed and opened again.
+     */
+    pub async fn get_quick_tune(&self, channel: BladerfChannel) -> Result<QuickTune> {
+        match self.board()? {
+            BladerfBoard::BladeRF1 => {
+                let dir = lms_direction(channel)?;
+                let mut tune = QuickTune::from(self.lms_get_tuning(dir).await?);
+
+                if let QuickTune::BladeRF1 { xb_gpio, .. } = &mut tune {
+                    *xb_gpio = xb200_quick_tune_gpio(nios_expansion_gpio_read(self).await?, dir);
+                }
+
+                Ok(tune)
+            }
+            BladerfBoard::BladeRF2 => {
+                let dir = channel.direction();
+
+                let nios_profile = self.quick_tune_profiles[dir as usize]
+                    .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |p| (p < NIOS_PROFILE_MAX).then_some(p + 1))
+                    .map_err(|_| BladerfError::QueueFull)?;
+                let rffe_profile = (nios_profile % RFFE_PROFILE_MAX) as u8;
+
+                rfic_write(self, RficCommand::Fastlock, channel, rffe_profile as u64).await?;
+                /* Copy the AD9361 profile into NIOS memory, where retunes load it from */
+                nios_rffe_fastlock_save(self, dir == BladerfDirection::TX, rffe_profile, nios_profile).await?;
+
+                let high_band = rfic_read(self, RficCommand::Frequency, channel).await? >= BLADERF2_BAND_SPLIT;
+                let rffe = nios_rffe_control_read(self).await?;
+
+                /* Both SPDT switch pairs of the direction, 2 bits per channel */
+                let spdt = match dir {
+                    BladerfDirection::RX => rffe >> RFFE_CONTROL_RX_SPDT_1,
+                    BladerfDirection::TX => rffe >> RFFE_CONTROL_TX_SPDT_1,
+                };
+
+                Ok(QuickTune::BladeRF2 {
+                    nios_profile,
+                    rffe_profile,
+                    port: if high_band { 0 } else { 1 },
+                    spdt: (spdt & 0xf) as u8,
+                })
+            }
+        }
+    }
+
+    /* Replay a captured tuning immediately */
+    pub async fn set_quick_tune(&self, channel: BladerfChannel, tune: &QuickTune) -> Result<()> {
+        self.retune(channel.direction(), NIOS_PKT_RETUNE_NOW, tune).await?;
+
+        Ok(())
+    }
+}
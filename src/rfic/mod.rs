@@ -0,0 +1,75 @@
+use crate::error::BladerfError;
+use crate::nios::nios_access::{nios_rfic_command_read, nios_rfic_command_write};
+use crate::{BladerfChannel, Device};
+use anyhow::Result;
+
+pub mod oversample;
+
+/* Commands understood by the bladeRF 2.0 NIOS RFIC controller */
+#[repr(u8)]
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum RficCommand {
+    Status = 0x00,
+    Init = 0x01,
+    Enable = 0x02,
+    SampleRate = 0x03,
+    Frequency = 0x04,
+    Bandwidth = 0x05,
+    GainMode = 0x06,
+    Gain = 0x07,
+    Rssi = 0x08,
+    Filter = 0x09,
+    TxMute = 0x0a,
+    Fastlock = 0x0b,
+}
+
+#[repr(u8)]
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum BladerfGainMode {
+    Default = 0,
+    Manual = 1,
+    FastAttackAgc = 2,
+    SlowAttackAgc = 3,
+    HybridAgc = 4,
+}
+
+impl TryFrom<u64> for BladerfGainMode {
+    type Error = anyhow::Error;
+
+    fn try_from(value: u64) -> Result<Self> {
+        match value {
+            0 => Ok(BladerfGainMode::Default),
+            1 => Ok(BladerfGainMode::Manual),
+            2 => Ok(BladerfGainMode::FastAttackAgc),
+            3 => Ok(BladerfGainMode::SlowAttackAgc),
+            4 => Ok(BladerfGainMode::HybridAgc),
+            _ => Err(BladerfError::Unexpected.into()),
+        }
+    }
+}
+
+/* FIR filter settings; RX values decimate and TX values interpolate */
+#[repr(u8)]
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum RficFir {
+    Bypass = 0,
+    Custom = 1,
+    X1 = 2,
+    X2 = 3,
+    X4 = 4,
+}
+
+/* Channel in bits 11:8, command in bits 7:0 */
+fn rfic_address(cmd: RficCommand, ch: BladerfChannel) -> u16 {
+    ((ch as u16 & 0xf) << 8) | cmd as u16
+}
+
+pub async fn rfic_read(dev: &Device, cmd: RficCommand, ch: BladerfChannel) -> Result<u64> {
+    nios_rfic_command_read(dev, rfic_address(cmd, ch)).await
+}
+
+pub async fn rfic_write(dev: &Device, cmd: RficCommand, ch: BladerfChannel, data: u64) -> Result<()> {
+    nios_rfic_command_write(dev, rfic_address(cmd, ch), data).await?;
+
+    Ok(())
+}
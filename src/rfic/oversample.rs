@@ -0,0 +1,126 @@
+use crate::error::BladerfError;
+use crate::nios::fpga_config::{fpga_config_modify, fpga_config_read};
+use crate::nios::nios_access::nios_get_fpga_version;
+use crate::rfic::*;
+use crate::stream::{SampleFormat, FPGA_VERSION_8BIT_SAMPLES};
+use crate::{BladerfBoard, BladerfVersion};
+use core::sync::atomic::Ordering;
+
+pub const OVERSAMPLE_SAMPLE_RATE: u64 = 122_880_000;
+
+/* RFIC settings changed by oversampling, in the order they are written */
+const OVERSAMPLE_SETTINGS: [(RficCommand, BladerfChannel); 4] = [
+    (RficCommand::Filter, BladerfChannel::RX1),
+    (RficCommand::Filter, BladerfChannel::TX1),
+    (RficCommand::SampleRate, BladerfChannel::RX1),
+    (RficCommand::SampleRate, BladerfChannel::TX1),
+];
+
+/*
+ * Only the packed 8-bit formats fit the oversample rate over USB, and the AGC
+ * cannot follow it, so every RX channel must be in manual gain mode.
+ */
+pub fn check_oversample(format: SampleFormat, fpga_version: BladerfVersion, rx_gain_modes: &[BladerfGainMode]) -> Result<()> {
+    if format.value_size() != 1 {
+        return Err(BladerfError::Inval.into());
+    }
+
+    if fpga_version < FPGA_VERSION_8BIT_SAMPLES {
+        return Err(BladerfError::Unsupported.into());
+    }
+
+    if rx_gain_modes.iter().any(|&mode| mode != BladerfGainMode::Manual) {
+        return Err(BladerfError::Inval.into());
+    }
+
+    Ok(())
+}
+
+#[cfg(feature = "std")]
+impl Device {
+    /*
+     * Run the AD9361 at 122.88 MSPS with its FIR filters bypassed, see check_oversample().
+     * If any step fails, the filters and sample rates in use before are written back.
+     */
+    pub async fn enable_oversample(&self, format: SampleFormat) -> Result<()> {
+        if self.board()? != BladerfBoard::BladeRF2 {
+            return Err(BladerfError::Unsupported.into());
+        }
+
+        let mut gain_modes = [BladerfGainMode::Default; 2];
+
+        for (mode, ch) in gain_modes.iter_mut().zip([BladerfChannel::RX1, BladerfChannel::RX2]) {
+            *mode = BladerfGainMode::try_from(rfic_read(self, RficCommand::GainMode, ch).await?)?;
+        }
+
+        check_oversample(format, nios_get_fpga_version(self).await?, &gain_modes)?;
+
+        let mut previous = [0; 4];
+
+        for (value, (cmd, ch)) in previous.iter_mut().zip(OVERSAMPLE_SETTINGS) {
+            *value = rfic_read(self, cmd, ch).await?;
+        }
+
+        let oversample = [RficFir::Bypass as u64, RficFir::Bypass as u64, OVERSAMPLE_SAMPLE_RATE, OVERSAMPLE_SAMPLE_RATE];
+
+        let result: Result<()> = async {
+            self.write_oversample_settings(oversample).await?;
+
+            fpga_config_modify(self, |reg| {
+                /* Keep the sample format in use before the first enable, for disable_oversample() */
+                if !reg.oversample {
+                    self.oversample_eight_bit.store(reg.eight_bit, Ordering::Relaxed);
+                }
+
+                reg.oversample = true;
+                reg.eight_bit = true;
+            })
+            .await?;
+
+            Ok(())
+        }
+        .await;
+
+        if result.is_err() {
+            /* The original error is more useful than one from the restore */
+            let _ = self.write_oversample_settings(previous).await;
+        }
+
+        result
+    }
+
+    /*
+     * Restore the default FIR filters, the prior sample format and run the RFIC at
+     * `sample_rate` again. The FPGA leaves oversample mode first, so if a later
+     * step fails the RFIC may still be in the oversample configuration and this
+     * can simply be called again.
+     */
+    pub async fn disable_oversample(&self, sample_rate: u64) -> Result<()> {
+        if self.board()? != BladerfBoard::BladeRF2 {
+            return Err(BladerfError::Unsupported.into());
+        }
+
+        fpga_config_modify(self, |reg| {
+            if reg.oversample {
+                reg.eight_bit = self.oversample_eight_bit.load(Ordering::Relaxed);
+            }
+
+            reg.oversample = false;
+        })
+        .await?;
+
+        self.write_oversample_settings([RficFir::X1 as u64, RficFir::X1 as u64, sample_rate, sample_rate]).await
+    }
+
+    async fn write_oversample_settings(&self, values: [u64; 4]) -> Result<()> {
+        for ((cmd, ch), value) in OVERSAMPLE_SETTINGS.into_iter().zip(values) {
+            rfic_write(self, cmd, ch, value).await?;
+        }
+
+        Ok(())
+    }
+
+    pub async fn is_oversample_enabled(&self) -> Result<bool> {
+        Ok(fpga_config_read(self).await?.oversample)
+    }
+}
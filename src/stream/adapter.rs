@@ -0,0 +1,52 @@
+use crate::stream::rx::RxStream;
+use crate::stream::tx::TxStream;
+use crate::stream::*;
+use alloc::vec;
+use futures::{Sink, Stream};
+
+#[derive(Clone, Debug, Default)]
+pub struct SampleBuffer {
+    /* Interleaved SC16Q11 samples (I, Q, I, Q, ...) */
+    pub samples: Vec<i16>,
+    pub meta: Metadata,
+}
+
+impl<'a, T: SampleTransport + 'a> RxStream<'a, T> {
+    /*
+     * Continuously read buffers of up to `samples_per_buffer` samples. A buffer is
+     * shorter when samples were lost, which is flagged in its meta.status. The
+     * stream ends after yielding the first error.
+     */
+    pub fn into_stream(self, samples_per_buffer: usize) -> impl Stream<Item = Result<SampleBuffer>> + 'a {
+        let vps = self.config().values_per_sample();
+
+        futures::stream::unfold(Some(self), move |rx| async move {
+            let mut rx = rx?;
+            let mut buf = SampleBuffer {
+                samples: vec![0; samples_per_buffer * vps],
+                meta: Metadata {
+                    flags: META_FLAG_RX_NOW,
+                    ..Default::default()
+                },
+            };
+
+            match rx.read(&mut buf.samples, &mut buf.meta).await {
+                Ok(()) => {
+                    buf.samples.truncate(buf.meta.actual_count * vps);
+                    Some((Ok(buf), Some(rx)))
+                }
+                Err(err) => Some((Err(err), None)),
+            }
+        })
+    }
+}
+
+impl<'a, T: SampleTransport + 'a> TxStream<'a, T> {
+    /* Each buffer is written with its own metadata, so bursts are framed by meta.flags */
+    pub fn into_sink(self) -> impl Sink<SampleBuffer, Error = anyhow::Error> + 'a {
+        futures::sink::unfold(self, |mut tx, mut buf: SampleBuffer| async move {
+            tx.write(&buf.samples, &mut buf.meta).await?;
+            Ok(tx)
+        })
+    }
+}
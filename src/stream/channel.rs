@@ -0,0 +1,67 @@
+use crate::error::BladerfError;
+use crate::stream::rx::RxStream;
+use crate::stream::tx::TxStream;
+use crate::stream::*;
+use alloc::vec;
+
+/* Split a buffer of interleaved sample times into one I/Q buffer per channel */
+pub fn deinterleave(samples: &[i16], channels: usize) -> Result<Vec<Vec<i16>>> {
+    if channels == 0 {
+        return Err(BladerfError::Inval.into());
+    }
+
+    let mut out = vec![Vec::with_capacity(samples.len() / channels); channels];
+
+    for frame in samples.chunks_exact(2 * channels) {
+        for (ch, iq) in frame.chunks_exact(2).enumerate() {
+            out[ch].extend_from_slice(iq);
+        }
+    }
+
+    Ok(out)
+}
+
+/* Merge per-channel I/Q buffers of equal length into interleaved sample times */
+pub fn interleave(channels: &[&[i16]]) -> Result<Vec<i16>> {
+    let len = channels.first().map_or(0, |ch| ch.len());
+
+    if !len.is_multiple_of(2) || channels.iter().any(|ch| ch.len() != len) {
+        return Err(BladerfError::Inval.into());
+    }
+
+    let mut out = Vec::with_capacity(len * channels.len());
+
+    for i in (0..len).step_by(2) {
+        for ch in channels {
+            out.extend_from_slice(&ch[i..i + 2]);
+        }
+    }
+
+    Ok(out)
+}
+
+impl<T: SampleTransport> RxStream<'_, T> {
+    /* Read `count` sample times and return them split per channel */
+    pub async fn read_channels(&mut self, count: usize, meta: &mut Metadata) -> Result<Vec<Vec<i16>>> {
+        let channels = self.config().channels;
+        let mut samples = vec![0; count * 2 * channels];
+
+        self.read(&mut samples, meta).await?;
+        samples.truncate(meta.actual_count * 2 * channels);
+
+        deinterleave(&samples, channels)
+    }
+}
+
+impl<T: SampleTransport> TxStream<'_, T> {
+    /* Write one I/Q buffer per channel, all covering the same sample times */
+    pub async fn write_channels(&mut self, channels: &[&[i16]], meta: &mut Metadata) -> Result<()> {
+        if channels.len() != self.config().channels {
+            return Err(BladerfError::Inval.into());
+        }
+
+        let samples = interleave(channels)?;
+
+        self.write(&samples, meta).await
+    }
+}
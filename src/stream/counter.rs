@@ -0,0 +1,72 @@
+use crate::error::BladerfError;
+use crate::nios::fpga_config::RxMux;
+use anyhow::Result;
+
+/*
+ * Validates samples produced by the FPGA counter RX mux modes, so the whole
+ * USB/streaming path can be checked without an RF signal.
+ *
+ * 32-bit counter: I holds the low and Q the high half of a counter that
+ * increments every sample. 12-bit counter: I counts up through the signed 12-bit
+ * range and wraps; Q is not checked.
+ */
+#[derive(Copy, Clone, Debug)]
+pub struct CounterChecker {
+    mode: RxMux,
+    expected: Option<u32>,
+
+    pub checked: u64,
+    pub discontinuities: u64,
+    /* Samples skipped by the counter, i.e. lost somewhere on the way to the host */
+    pub dropped: u64,
+}
+
+impl CounterChecker {
+    pub fn new(mode: RxMux) -> Result<Self> {
+        match mode {
+            RxMux::Counter12Bit | RxMux::Counter32Bit => Ok(CounterChecker {
+                mode,
+                expected: None,
+                checked: 0,
+                discontinuities: 0,
+                dropped: 0,
+            }),
+            _ => Err(BladerfError::Inval.into()),
+        }
+    }
+
+    /* Check a buffer of interleaved I/Q samples, returning the discontinuities found in it */
+    pub fn check(&mut self, samples: &[i16]) -> u64 {
+        let before = self.discontinuities;
+
+        for iq in samples.chunks_exact(2) {
+            let (value, modulus) = match self.mode {
+                RxMux::Counter12Bit => ((iq[0] as u32) & 0xfff, 1 << 12),
+                _ => (((iq[1] as u16 as u32) << 16) | iq[0] as u16 as u32, 1 << 32),
+            };
+
+            if let Some(expected) = self.expected {
+                if value != expected {
+                    self.discontinuities += 1;
+                    self.dropped += (value as u64 + modulus - expected as u64) % modulus;
+                }
+            }
+
+            self.expected = Some(((value as u64 + 1) % modulus) as u32);
+            self.checked += 1;
+        }
+
+        self.discontinuities - before
+    }
+
+    /* Fraction of samples lost, over everything checked so far */
+    pub fn drop_rate(&self) -> f64 {
+        let total = self.checked + self.dropped;
+
+        if total == 0 {
+            0.0
+        } else {
+            self.dropped as f64 / total as f64
+        }
+    }
+}
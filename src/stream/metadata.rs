@@ -0,0 +1,49 @@
+/* Header prepended to every message of a *_META sample format */
+const METADATA_IDX_RESV: usize = 0;
+/* In PACKET_META mode the reserved word holds the packet length and core ID */
+const METADATA_IDX_PACKET_LEN: usize = 0;
+const METADATA_IDX_PACKET_CORE: usize = 2;
+const METADATA_IDX_TIMESTAMP: usize = 4;
+const METADATA_IDX_FLAGS: usize = 12;
+
+pub const METADATA_HEADER_SIZE: usize = 16;
+
+/* Set by the FPGA in an RX header when its FIFO overflowed and samples were dropped */
+pub const METADATA_FLAG_RX_HW_OVERRUN: u32 = 1 << 0;
+
+pub fn pack_metadata(timestamp: u64, flags: u32) -> [u8; METADATA_HEADER_SIZE] {
+    let mut buf = [0; METADATA_HEADER_SIZE];
+
+    buf[METADATA_IDX_RESV..METADATA_IDX_TIMESTAMP].copy_from_slice(&0u32.to_le_bytes());
+    buf[METADATA_IDX_TIMESTAMP..METADATA_IDX_FLAGS].copy_from_slice(&timestamp.to_le_bytes());
+    buf[METADATA_IDX_FLAGS..METADATA_HEADER_SIZE].copy_from_slice(&flags.to_le_bytes());
+
+    buf
+}
+
+pub fn unpack_metadata(buf: &[u8]) -> (u64, u32) {
+    let mut timestamp = [0; 8];
+    let mut flags = [0; 4];
+
+    timestamp.copy_from_slice(&buf[METADATA_IDX_TIMESTAMP..METADATA_IDX_FLAGS]);
+    flags.copy_from_slice(&buf[METADATA_IDX_FLAGS..METADATA_HEADER_SIZE]);
+
+    (u64::from_le_bytes(timestamp), u32::from_le_bytes(flags))
+}
+
+pub fn pack_packet_metadata(timestamp: u64, flags: u32, length: u16, core: u8) -> [u8; METADATA_HEADER_SIZE] {
+    let mut buf = pack_metadata(timestamp, flags);
+
+    buf[METADATA_IDX_PACKET_LEN..METADATA_IDX_PACKET_CORE].copy_from_slice(&length.to_le_bytes());
+    buf[METADATA_IDX_PACKET_CORE] = core;
+
+    buf
+}
+
+pub fn unpack_packet_metadata(buf: &[u8]) -> (u64, u32, u16, u8) {
+    let (timestamp, flags) = unpack_metadata(buf);
+    let length = u16::from_le_bytes([buf[METADATA_IDX_PACKET_LEN], buf[METADATA_IDX_PACKET_LEN + 1]]);
+    let core = buf[METADATA_IDX_PACKET_CORE];
+
+    (timestamp, flags, length, core)
+}
@@ -0,0 +1,277 @@
+use crate::error::BladerfError;
+use crate::nios::fpga_config::fpga_config_modify;
+use crate::nios::nios_access::{nios_get_fpga_version, nios_get_timestamp};
+use crate::usb::{bulk_transfer_in_vec, bulk_transfer_out};
+use crate::{BladerfBoard, BladerfChannelLayout, BladerfDirection, BladerfVersion, Device};
+use alloc::vec::Vec;
+use anyhow::Result;
+use core::future::Future;
+use metadata::METADATA_HEADER_SIZE;
+use packet::PacketStream;
+use rx::RxStream;
+use tx::TxStream;
+
+pub mod adapter;
+pub mod channel;
+pub mod counter;
+pub mod metadata;
+pub mod packet;
+pub mod rx;
+pub mod stats;
+pub mod tx;
+
+pub const SAMPLE_EP_RX: u8 = 0x81;
+pub const SAMPLE_EP_TX: u8 = 0x01;
+
+/* Message sizes used by the FPGA on SuperSpeed and HighSpeed links */
+pub const MESSAGE_SIZE_SS: usize = 2048;
+pub const MESSAGE_SIZE_HS: usize = 1024;
+
+/* First FPGA version able to pack samples into 8 bits */
+pub(crate) const FPGA_VERSION_8BIT_SAMPLES: BladerfVersion = BladerfVersion { major: 0, minor: 15, patch: 0 };
+/* First FPGA version able to exchange PACKET_META messages */
+const FPGA_VERSION_PACKET_META: BladerfVersion = BladerfVersion { major: 0, minor: 12, patch: 0 };
+
+/* Metadata flags, matching libbladerf's BLADERF_META_FLAG_* values */
+pub const META_FLAG_TX_BURST_START: u32 = 1 << 0;
+pub const META_FLAG_TX_BURST_END: u32 = 1 << 1;
+pub const META_FLAG_TX_NOW: u32 = 1 << 2;
+pub const META_FLAG_TX_UPDATE_TIMESTAMP: u32 = 1 << 3;
+pub const META_FLAG_RX_NOW: u32 = 1 << 31;
+
+pub const META_STATUS_OVERRUN: u32 = 1 << 0;
+pub const META_STATUS_UNDERRUN: u32 = 1 << 1;
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Default)]
+pub struct Metadata {
+    pub timestamp: u64,
+    pub flags: u32,
+    pub status: u32,
+    pub actual_count: usize,
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum SampleFormat {
+    /* 16-bit I and Q values */
+    Sc16Q11,
+    Sc16Q11Meta,
+    /* 8-bit I and Q values, bladeRF 2.0 with FPGA v0.15.0 or later */
+    Sc8Q7,
+    Sc8Q7Meta,
+    /* Framed packets of 32-bit words for custom FPGA cores, FPGA v0.12.0 or later */
+    PacketMeta,
+}
+
+impl SampleFormat {
+    pub fn has_metadata(&self) -> bool {
+        matches!(self, SampleFormat::Sc16Q11Meta | SampleFormat::Sc8Q7Meta | SampleFormat::PacketMeta)
+    }
+
+    /* Bytes per I or Q value, or per payload word for packets */
+    pub fn value_size(&self) -> usize {
+        match self {
+            SampleFormat::Sc16Q11 | SampleFormat::Sc16Q11Meta => 2,
+            SampleFormat::Sc8Q7 | SampleFormat::Sc8Q7Meta => 1,
+            SampleFormat::PacketMeta => 4,
+        }
+    }
+}
+
+/* An I or Q value as it is laid out on the wire */
+pub(crate) trait Sample: Copy {
+    const SIZE: usize;
+
+    fn from_le(bytes: &[u8]) -> Self;
+
+    fn to_le(self, bytes: &mut [u8]);
+}
+
+impl Sample for i16 {
+    const SIZE: usize = 2;
+
+    fn from_le(bytes: &[u8]) -> Self {
+        i16::from_le_bytes([bytes[0], bytes[1]])
+    }
+
+    fn to_le(self, bytes: &mut [u8]) {
+        bytes.copy_from_slice(&self.to_le_bytes());
+    }
+}
+
+impl Sample for i8 {
+    const SIZE: usize = 1;
+
+    fn from_le(bytes: &[u8]) -> Self {
+        bytes[0] as i8
+    }
+
+    fn to_le(self, bytes: &mut [u8]) {
+        bytes[0] = self as u8;
+    }
+}
+
+/*
+ * With a *_META format every message starts with a metadata header carrying its
+ * timestamp. Without one, timestamps are counted on the host from the first
+ * message and cannot be used to schedule TX or detect lost samples. With two
+ * channels (the RX_X2/TX_X2 layouts) each sample time carries one sample
+ * per channel, and timestamps count sample times rather than individual samples.
+ */
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub struct StreamConfig {
+    pub message_size: usize,
+    pub channels: usize,
+    pub format: SampleFormat,
+}
+
+impl Default for StreamConfig {
+    fn default() -> Self {
+        StreamConfig {
+            message_size: MESSAGE_SIZE_SS,
+            channels: 1,
+            format: SampleFormat::Sc16Q11Meta,
+        }
+    }
+}
+
+impl StreamConfig {
+    pub fn for_layout(layout: BladerfChannelLayout) -> Self {
+        StreamConfig {
+            channels: layout.num_channels(),
+            ..Default::default()
+        }
+    }
+
+    pub fn header_size(&self) -> usize {
+        if self.format.has_metadata() {
+            METADATA_HEADER_SIZE
+        } else {
+            0
+        }
+    }
+
+    /* Bytes per sample time, across all channels */
+    pub fn sample_size(&self) -> usize {
+        self.format.value_size() * self.values_per_sample()
+    }
+
+    /* I and Q values per sample time, across all channels */
+    pub fn values_per_sample(&self) -> usize {
+        2 * self.channels
+    }
+
+    /*
+     * Streams carry one channel, or two for the bladeRF 2.0 MIMO layouts, and each
+     * message must hold a whole number of sample times after its header
+     */
+    pub fn validate(&self) -> Result<()> {
+        if !(1..=2).contains(&self.channels) {
+            return Err(BladerfError::Inval.into());
+        }
+
+        let payload = self.message_size.saturating_sub(self.header_size());
+
+        if payload == 0 || !payload.is_multiple_of(self.sample_size()) {
+            return Err(BladerfError::Inval.into());
+        }
+
+        Ok(())
+    }
+
+    pub fn samples_per_message(&self) -> usize {
+        (self.message_size - self.header_size()) / self.sample_size()
+    }
+}
+
+pub trait SampleTransport {
+    fn read_message(&self, len: usize) -> impl Future<Output = Result<Vec<u8>>>;
+
+    fn write_message(&self, buf: &[u8]) -> impl Future<Output = Result<()>>;
+
+    fn get_timestamp(&self, dir: BladerfDirection) -> impl Future<Output = Result<u64>>;
+}
+
+/*
+ * Independent RX and TX handles on one transport. Both run on the device's sample
+ * clock, so RX timestamps can be used directly to schedule TX bursts.
+ */
+pub fn duplex<T: SampleTransport>(transport: &T, config: StreamConfig) -> Result<(RxStream<'_, T>, TxStream<'_, T>)> {
+    Ok((RxStream::new(transport, config)?, TxStream::new(transport, config)?))
+}
+
+#[cfg(feature = "nusb")]
+impl SampleTransport for Device {
+    async fn read_message(&self, len: usize) -> Result<Vec<u8>> {
+        bulk_transfer_in_vec::<SAMPLE_EP_RX>(self, len).await
+    }
+
+    async fn write_message(&self, buf: &[u8]) -> Result<()> {
+        bulk_transfer_out::<SAMPLE_EP_TX>(self, buf).await
+    }
+
+    async fn get_timestamp(&self, dir: BladerfDirection) -> Result<u64> {
+        nios_get_timestamp(self, dir).await
+    }
+}
+
+#[cfg(feature = "nusb")]
+impl Device {
+    pub async fn sync_config(&self, config: &StreamConfig) -> Result<()> {
+        self.check_stream_config(config)?;
+
+        let format = config.format;
+
+        if format.value_size() == 1 && nios_get_fpga_version(self).await? < FPGA_VERSION_8BIT_SAMPLES {
+            return Err(BladerfError::Unsupported.into());
+        }
+
+        if format == SampleFormat::PacketMeta && nios_get_fpga_version(self).await? < FPGA_VERSION_PACKET_META {
+            return Err(BladerfError::Unsupported.into());
+        }
+
+        fpga_config_modify(self, |reg| {
+            /* The metadata headers carry timestamps only while the FPGA counters run */
+            reg.timestamps = format.has_metadata();
+            reg.eight_bit = format.value_size() == 1;
+            reg.packet = format == SampleFormat::PacketMeta;
+        })
+        .await?;
+
+        Ok(())
+    }
+
+    /* The bladeRF 1 has a single RX and TX channel */
+    fn check_stream_config(&self, config: &StreamConfig) -> Result<()> {
+        config.validate()?;
+
+        if config.channels > 1 && self.board()? == BladerfBoard::BladeRF1 {
+            return Err(BladerfError::Inval.into());
+        }
+
+        Ok(())
+    }
+
+    pub fn rx_stream(&self, config: StreamConfig) -> Result<RxStream<'_, Device>> {
+        self.check_stream_config(&config)?;
+
+        RxStream::new(self, config)
+    }
+
+    pub fn tx_stream(&self, config: StreamConfig) -> Result<TxStream<'_, Device>> {
+        self.check_stream_config(&config)?;
+
+        TxStream::new(self, config)
+    }
+
+    pub fn packet_stream(&self, config: StreamConfig) -> Result<PacketStream<'_, Device>> {
+        self.check_stream_config(&config)?;
+
+        PacketStream::new(self, config)
+    }
+
+    pub fn duplex_streams(&self, config: StreamConfig) -> Result<(RxStream<'_, Device>, TxStream<'_, Device>)> {
+        self.check_stream_config(&config)?;
+
+        duplex(self, config)
+    }
+}
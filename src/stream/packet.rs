@@ -0,0 +1,85 @@
+use crate::error::BladerfError;
+use crate::stream::metadata::{pack_packet_metadata, unpack_packet_metadata, METADATA_HEADER_SIZE};
+use crate::stream::*;
+use alloc::vec;
+
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Packet {
+    /* ID of the FPGA core the packet is addressed to or came from */
+    pub core: u8,
+    /* Core-defined packet flags, carried in the metadata flags word */
+    pub flags: u32,
+    pub timestamp: u64,
+    pub payload: Vec<u32>,
+}
+
+/* PACKET_META framing: one packet per message, its length given in 32-bit words */
+pub struct PacketStream<'a, T: SampleTransport> {
+    transport: &'a T,
+    config: StreamConfig,
+}
+
+impl<'a, T: SampleTransport> PacketStream<'a, T> {
+    pub fn new(transport: &'a T, config: StreamConfig) -> Result<Self> {
+        config.validate()?;
+
+        if config.format != SampleFormat::PacketMeta {
+            return Err(BladerfError::Inval.into());
+        }
+
+        Ok(PacketStream { transport, config })
+    }
+
+    pub fn max_payload_words(&self) -> usize {
+        (self.config.message_size - METADATA_HEADER_SIZE) / 4
+    }
+
+    pub async fn send(&mut self, packet: &Packet) -> Result<()> {
+        if packet.payload.len() > self.max_payload_words() {
+            return Err(BladerfError::Inval.into());
+        }
+
+        let mut msg = vec![0; self.config.message_size];
+
+        msg[..METADATA_HEADER_SIZE].copy_from_slice(&pack_packet_metadata(
+            packet.timestamp,
+            packet.flags,
+            packet.payload.len() as u16,
+            packet.core,
+        ));
+
+        for (i, word) in packet.payload.iter().enumerate() {
+            let offset = METADATA_HEADER_SIZE + i * 4;
+            msg[offset..offset + 4].copy_from_slice(&word.to_le_bytes());
+        }
+
+        self.transport.write_message(&msg).await
+    }
+
+    pub async fn recv(&mut self) -> Result<Packet> {
+        let msg = self.transport.read_message(self.config.message_size).await?;
+
+        if msg.len() < METADATA_HEADER_SIZE {
+            return Err(BladerfError::Io.into());
+        }
+
+        let (timestamp, flags, length, core) = unpack_packet_metadata(&msg);
+        let length = length as usize;
+
+        if METADATA_HEADER_SIZE + length * 4 > msg.len() {
+            return Err(BladerfError::Unexpected.into());
+        }
+
+        let payload = msg[METADATA_HEADER_SIZE..METADATA_HEADER_SIZE + length * 4]
+            .chunks_exact(4)
+            .map(|word| u32::from_le_bytes([word[0], word[1], word[2], word[3]]))
+            .collect();
+
+        Ok(Packet {
+            core,
+            flags,
+            timestamp,
+            payload,
+        })
+    }
+}
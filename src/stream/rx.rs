@@ -0,0 +1,171 @@
+use crate::error::BladerfError;
+use crate::stream::metadata::{unpack_metadata, METADATA_FLAG_RX_HW_OVERRUN};
+use crate::stream::stats::StreamStats;
+use crate::stream::*;
+use alloc::vec::Vec;
+use anyhow::Result;
+
+pub struct RxStream<'a, T: SampleTransport> {
+    transport: &'a T,
+    config: StreamConfig,
+
+    msg: Vec<u8>,
+    msg_timestamp: u64,
+    /* Index of the next unread sample in msg */
+    msg_pos: usize,
+
+    /* Timestamp the next message header is expected to carry */
+    next_timestamp: Option<u64>,
+
+    stats: StreamStats,
+}
+
+impl<'a, T: SampleTransport> RxStream<'a, T> {
+    pub fn new(transport: &'a T, config: StreamConfig) -> Result<Self> {
+        config.validate()?;
+
+        Ok(RxStream {
+            transport,
+            config,
+            msg: Vec::new(),
+            msg_timestamp: 0,
+            msg_pos: config.samples_per_message(),
+            next_timestamp: None,
+            stats: StreamStats::default(),
+        })
+    }
+
+    pub fn stats(&self) -> StreamStats {
+        self.stats
+    }
+
+    pub fn reset_stats(&mut self) {
+        self.stats = StreamStats::default();
+    }
+
+    pub fn config(&self) -> StreamConfig {
+        self.config
+    }
+
+    /* Timestamp of the next sample that will be returned */
+    pub fn timestamp(&self) -> u64 {
+        self.msg_timestamp + self.msg_pos as u64
+    }
+
+    /*
+     * Read interleaved SC16Q11 samples into `samples`, with the channels of each
+     * sample time next to each other. Without META_FLAG_RX_NOW the read starts
+     * exactly at meta.timestamp, discarding everything before it. On return
+     * meta.timestamp holds the timestamp of the first sample, and meta.actual_count
+     * the number of sample times read. A read stops early when samples were lost (a
+     * gap between message headers, or the FPGA flagging an overrun) and reports it
+     * with META_STATUS_OVERRUN; the samples after the loss are returned by the next
+     * read.
+     */
+    pub async fn read(&mut self, samples: &mut [i16], meta: &mut Metadata) -> Result<()> {
+        self.read_values(samples, meta).await
+    }
+
+    /* As read(), for the 8-bit SC8Q7 formats */
+    pub async fn read_sc8(&mut self, samples: &mut [i8], meta: &mut Metadata) -> Result<()> {
+        self.read_values(samples, meta).await
+    }
+
+    async fn read_values<S: Sample>(&mut self, samples: &mut [S], meta: &mut Metadata) -> Result<()> {
+        let vps = self.config.values_per_sample();
+
+        if S::SIZE != self.config.format.value_size() || !samples.len().is_multiple_of(vps) {
+            return Err(BladerfError::Inval.into());
+        }
+
+        let spm = self.config.samples_per_message();
+        let count = samples.len() / vps;
+
+        meta.status = 0;
+        meta.actual_count = 0;
+
+        if meta.flags & META_FLAG_RX_NOW != 0 {
+            if self.msg_pos == spm && self.fetch().await? {
+                meta.status |= META_STATUS_OVERRUN;
+            }
+        } else {
+            let target = meta.timestamp;
+
+            loop {
+                if self.msg_pos == spm {
+                    self.fetch().await?;
+                }
+
+                let now = self.timestamp();
+                if now > target {
+                    return Err(BladerfError::TimePast.into());
+                }
+
+                let skip = ((target - now) as usize).min(spm - self.msg_pos);
+                self.msg_pos += skip;
+
+                if self.msg_pos < spm {
+                    break;
+                }
+            }
+        }
+
+        meta.timestamp = self.timestamp();
+
+        let mut copied = 0;
+        while copied < count {
+            if self.msg_pos == spm && self.fetch().await? {
+                meta.status |= META_STATUS_OVERRUN;
+                break;
+            }
+
+            let n = (spm - self.msg_pos).min(count - copied);
+            let offset = self.config.header_size() + self.msg_pos * self.config.sample_size();
+
+            for (i, value) in samples[copied * vps..(copied + n) * vps].iter_mut().enumerate() {
+                *value = S::from_le(&self.msg[offset + i * S::SIZE..]);
+            }
+
+            self.msg_pos += n;
+            copied += n;
+        }
+
+        meta.actual_count = copied;
+
+        Ok(())
+    }
+
+    /* Fetch the next message, returning true if samples were lost before it */
+    async fn fetch(&mut self) -> Result<bool> {
+        let msg = self.transport.read_message(self.config.message_size).await?;
+
+        if msg.len() != self.config.message_size {
+            return Err(BladerfError::Io.into());
+        }
+
+        let (timestamp, flags) = if self.config.format.has_metadata() {
+            unpack_metadata(&msg)
+        } else {
+            (self.next_timestamp.unwrap_or(0), 0)
+        };
+        let discontinuity = self.next_timestamp.is_some_and(|expected| expected != timestamp);
+        let overrun = flags & METADATA_FLAG_RX_HW_OVERRUN != 0;
+
+        self.stats.record_buffer(self.config.samples_per_message());
+
+        if discontinuity || overrun {
+            self.stats.overruns += 1;
+        }
+
+        if let Some(expected) = self.next_timestamp.filter(|expected| timestamp > *expected) {
+            self.stats.dropped_samples += timestamp - expected;
+        }
+
+        self.msg = msg;
+        self.msg_timestamp = timestamp;
+        self.msg_pos = 0;
+        self.next_timestamp = Some(timestamp + self.config.samples_per_message() as u64);
+
+        Ok(discontinuity || overrun)
+    }
+}
@@ -0,0 +1,44 @@
+use std::time::Instant;
+
+#[derive(Copy, Clone, Debug, Default)]
+pub struct StreamStats {
+    pub samples: u64,
+    pub buffers: u64,
+    pub overruns: u64,
+    pub underruns: u64,
+    /* Samples known to be lost, from timestamp jumps between messages */
+    pub dropped_samples: u64,
+
+    first_buffer: Option<Instant>,
+    first_samples: u64,
+    last_buffer: Option<Instant>,
+}
+
+impl StreamStats {
+    pub(crate) fn record_buffer(&mut self, samples: usize) {
+        let now = Instant::now();
+
+        if self.first_buffer.is_none() {
+            self.first_buffer = Some(now);
+            self.first_samples = samples as u64;
+        }
+
+        self.last_buffer = Some(now);
+
+        self.buffers += 1;
+        self.samples += samples as u64;
+    }
+
+    /*
+     * Samples per second between the first and the most recent buffer. The first
+     * buffer only starts the clock, so its samples are not counted.
+     */
+    pub fn throughput(&self) -> f64 {
+        match (self.first_buffer, self.last_buffer) {
+            (Some(first), Some(last)) if last > first => {
+                (self.samples - self.first_samples) as f64 / (last - first).as_secs_f64()
+            }
+            _ => 0.0,
+        }
+    }
+}
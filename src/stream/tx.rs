@@ -0,0 +1,224 @@
+use crate::error::BladerfError;
+use crate::stream::metadata::{pack_metadata, METADATA_HEADER_SIZE};
+use crate::stream::stats::StreamStats;
+use crate::stream::*;
+use alloc::vec;
+use alloc::vec::Vec;
+use anyhow::Result;
+
+pub struct TxStream<'a, T: SampleTransport> {
+    transport: &'a T,
+    config: StreamConfig,
+
+    msg: Vec<u8>,
+    msg_open: bool,
+    msg_timestamp: u64,
+    msg_samples: usize,
+
+    /* Timestamp of the next sample to be placed on the wire */
+    curr_timestamp: u64,
+    in_burst: bool,
+    /* The burst was scheduled on the hardware timestamp rather than sent NOW */
+    scheduled: bool,
+
+    stats: StreamStats,
+}
+
+impl<'a, T: SampleTransport> TxStream<'a, T> {
+    pub fn new(transport: &'a T, config: StreamConfig) -> Result<Self> {
+        config.validate()?;
+
+        Ok(TxStream {
+            transport,
+            config,
+            msg: vec![0; config.message_size],
+            msg_open: false,
+            msg_timestamp: 0,
+            msg_samples: 0,
+            curr_timestamp: 0,
+            in_burst: false,
+            scheduled: false,
+            stats: StreamStats::default(),
+        })
+    }
+
+    pub fn stats(&self) -> StreamStats {
+        self.stats
+    }
+
+    pub fn reset_stats(&mut self) {
+        self.stats = StreamStats::default();
+    }
+
+    pub fn config(&self) -> StreamConfig {
+        self.config
+    }
+
+    pub fn timestamp(&self) -> u64 {
+        self.curr_timestamp
+    }
+
+    pub fn in_burst(&self) -> bool {
+        self.in_burst
+    }
+
+    /*
+     * Write interleaved SC16Q11 samples (I, Q, I, Q, ...), with the channels of each
+     * sample time next to each other. A burst is opened with META_FLAG_TX_BURST_START
+     * at meta.timestamp (or as soon as possible with META_FLAG_TX_NOW) and closed
+     * with META_FLAG_TX_BURST_END, at which point the rest of the message is padded
+     * with zeros. meta.status gets META_STATUS_UNDERRUN if a message of a scheduled
+     * burst reached the FPGA after its timestamp.
+     */
+    pub async fn write(&mut self, samples: &[i16], meta: &mut Metadata) -> Result<()> {
+        self.write_values(samples, meta).await
+    }
+
+    /* As write(), for the 8-bit SC8Q7 formats */
+    pub async fn write_sc8(&mut self, samples: &[i8], meta: &mut Metadata) -> Result<()> {
+        self.write_values(samples, meta).await
+    }
+
+    async fn write_values<S: Sample>(&mut self, samples: &[S], meta: &mut Metadata) -> Result<()> {
+        let underruns = self.stats.underruns;
+        meta.status = 0;
+
+        self.write_messages(samples, meta).await?;
+
+        if self.stats.underruns > underruns {
+            meta.status |= META_STATUS_UNDERRUN;
+        }
+
+        Ok(())
+    }
+
+    async fn write_messages<S: Sample>(&mut self, samples: &[S], meta: &Metadata) -> Result<()> {
+        let has_metadata = self.config.format.has_metadata();
+        let vps = self.config.values_per_sample();
+
+        if S::SIZE != self.config.format.value_size() || !samples.len().is_multiple_of(vps) {
+            return Err(BladerfError::Inval.into());
+        }
+
+        if meta.flags & META_FLAG_TX_BURST_START != 0 {
+            if self.in_burst {
+                return Err(BladerfError::Inval.into());
+            }
+
+            let now = if has_metadata {
+                self.transport.get_timestamp(BladerfDirection::TX).await?
+            } else {
+                0
+            };
+
+            /* Without metadata the FPGA cannot schedule, so bursts go out immediately */
+            let start = if !has_metadata {
+                self.curr_timestamp
+            } else if meta.flags & META_FLAG_TX_NOW != 0 {
+                now.max(self.curr_timestamp)
+            } else if meta.timestamp < now || meta.timestamp < self.curr_timestamp {
+                return Err(BladerfError::TimePast.into());
+            } else {
+                meta.timestamp
+            };
+
+            self.open_message(start);
+            self.in_burst = true;
+            self.scheduled = has_metadata && meta.flags & META_FLAG_TX_NOW == 0;
+        } else if !self.in_burst {
+            return Err(BladerfError::Inval.into());
+        } else if meta.flags & META_FLAG_TX_UPDATE_TIMESTAMP != 0 {
+            self.pad_to(meta.timestamp).await?;
+        }
+
+        let spm = self.config.samples_per_message();
+        let mut remaining = samples;
+
+        while !remaining.is_empty() {
+            if !self.msg_open {
+                self.open_message(self.curr_timestamp);
+            }
+
+            let count = (spm - self.msg_samples).min(remaining.len() / vps);
+            let offset = self.config.header_size() + self.msg_samples * self.config.sample_size();
+
+            for (i, value) in remaining[..count * vps].iter().enumerate() {
+                value.to_le(&mut self.msg[offset + i * S::SIZE..offset + (i + 1) * S::SIZE]);
+            }
+
+            self.msg_samples += count;
+            self.curr_timestamp += count as u64;
+            remaining = &remaining[count * vps..];
+
+            if self.msg_samples == spm {
+                self.flush().await?;
+            }
+        }
+
+        if meta.flags & META_FLAG_TX_BURST_END != 0 {
+            /* Always leave at least one zero sample so the DAC returns to zero */
+            if !self.msg_open {
+                self.open_message(self.curr_timestamp);
+            }
+
+            self.flush().await?;
+            self.in_burst = false;
+        }
+
+        Ok(())
+    }
+
+    fn open_message(&mut self, timestamp: u64) {
+        self.msg.fill(0);
+        self.msg_open = true;
+        self.msg_timestamp = timestamp;
+        self.msg_samples = 0;
+        self.curr_timestamp = timestamp;
+    }
+
+    /* Skip forward to a later timestamp inside a burst, filling the gap with zeros */
+    async fn pad_to(&mut self, timestamp: u64) -> Result<()> {
+        if timestamp < self.curr_timestamp {
+            return Err(BladerfError::TimePast.into());
+        }
+
+        let gap = timestamp - self.curr_timestamp;
+
+        if self.msg_open && gap < (self.config.samples_per_message() - self.msg_samples) as u64 {
+            /* The message buffer is already zeroed */
+            self.msg_samples += gap as usize;
+            self.curr_timestamp = timestamp;
+        } else {
+            if self.msg_open {
+                self.flush().await?;
+            }
+
+            self.open_message(timestamp);
+        }
+
+        Ok(())
+    }
+
+    async fn flush(&mut self) -> Result<()> {
+        if self.config.format.has_metadata() {
+            self.msg[..METADATA_HEADER_SIZE].copy_from_slice(&pack_metadata(self.msg_timestamp, 0));
+        }
+
+        /*
+         * If the hardware is already past this message, the FPGA ran out of samples
+         * before it arrived. NOW bursts start at an estimate of the current time,
+         * so their messages are always somewhat behind and are not checked.
+         */
+        if self.scheduled && self.transport.get_timestamp(BladerfDirection::TX).await? > self.msg_timestamp {
+            self.stats.underruns += 1;
+        }
+
+        self.transport.write_message(&self.msg).await?;
+        self.stats.record_buffer(self.config.samples_per_message());
+
+        self.msg_open = false;
+        self.curr_timestamp = self.msg_timestamp + self.config.samples_per_message() as u64;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,189 @@
+use crate::error::BladerfError;
+use crate::stream::rx::RxStream;
+use crate::stream::{Metadata, SampleTransport, StreamConfig, META_FLAG_RX_NOW};
+use crate::trigger::{Trigger, TriggerRole, TriggerSignal, TriggerTransport};
+use crate::{BladerfChannel, BladerfDirection, Device};
+use alloc::vec;
+use alloc::vec::Vec;
+use anyhow::Result;
+use core::future::Future;
+use std::time::{Duration, Instant};
+
+/* Time after firing within which every board must have seen the trigger */
+const TRIGGER_TIMEOUT: Duration = Duration::from_millis(250);
+
+/*
+ * Per-device timestamp offsets relative to the first (reference) device. Boards
+ * sharing a reference clock count at the same rate, so a single offset maps each
+ * board's counter onto the common time domain of the reference.
+ */
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct TimeAlignment {
+    pub offsets: Vec<i64>,
+    /* Largest spread between captures seen for any device, a bound on their jitter */
+    pub spread: u64,
+}
+
+impl TimeAlignment {
+    /* Each capture holds one timestamp per device, taken at the same instant, e.g. by TimebaseCoordinator::align() */
+    pub fn from_captures(captures: &[Vec<u64>]) -> Result<Self> {
+        let devices = captures.first().map_or(0, |c| c.len());
+
+        if devices == 0 || captures.iter().any(|c| c.len() != devices) {
+            return Err(BladerfError::Inval.into());
+        }
+
+        let mut offsets = Vec::with_capacity(devices);
+        let mut spread = 0;
+
+        for dev in 0..devices {
+            let mut round: Vec<i64> = captures.iter().map(|c| c[dev].wrapping_sub(c[0]) as i64).collect();
+            round.sort_unstable();
+
+            spread = spread.max(round[round.len() - 1].abs_diff(round[0]));
+            offsets.push(round[round.len() / 2]);
+        }
+
+        Ok(TimeAlignment { offsets, spread })
+    }
+
+    /* Convert a timestamp in the common time domain into device `dev`'s counter */
+    pub fn to_device(&self, dev: usize, timestamp: u64) -> u64 {
+        timestamp.wrapping_add_signed(self.offsets[dev])
+    }
+
+    /* Convert device `dev`'s counter value into the common time domain */
+    pub fn to_common(&self, dev: usize, timestamp: u64) -> u64 {
+        timestamp.wrapping_add_signed(-self.offsets[dev])
+    }
+}
+
+/* What the coordinator needs from each board, implemented by Device */
+pub trait TimebaseTransport: TriggerTransport + SampleTransport {
+    fn enable_rx(&self) -> impl Future<Output = Result<()>>;
+}
+
+#[cfg(feature = "nusb")]
+impl TimebaseTransport for Device {
+    async fn enable_rx(&self) -> Result<()> {
+        Device::enable_rx(self).await
+    }
+}
+
+/*
+ * Aligns the timebases of several boards wired to a shared reference clock and
+ * trigger line. The first device is the trigger master and the time reference.
+ *
+ * What is measured: an armed RX trigger holds back each board's sample flow until
+ * the trigger line goes active, so the first RX message a board delivers after
+ * firing starts at the sample taken on the trigger edge. The edge reaches every
+ * board at the same instant, so the differences between those header timestamps
+ * are the offsets between the boards' counters, free of USB latency.
+ *
+ * Every board must have its RX stream configured with a *_META format through
+ * sync_config(), with RX not yet enabled: align() enables it after arming, as
+ * libbladerf's trigger example does, and leaves it running.
+ */
+pub struct TimebaseCoordinator<'a, T: TimebaseTransport> {
+    devices: &'a [T],
+    channel: BladerfChannel,
+    signal: TriggerSignal,
+    config: StreamConfig,
+}
+
+impl<'a, T: TimebaseTransport> TimebaseCoordinator<'a, T> {
+    /* `config` is the RX stream configuration the boards were set up with */
+    pub fn new(devices: &'a [T], channel: BladerfChannel, signal: TriggerSignal, config: StreamConfig) -> Self {
+        TimebaseCoordinator {
+            devices,
+            channel,
+            signal,
+            config,
+        }
+    }
+
+    /*
+     * Fire the trigger across all boards and read the timestamp of the first gated
+     * RX message on each. Every board armed along the way is disarmed again, also
+     * when the alignment fails, so no sample stream is left gated on the trigger.
+     */
+    pub async fn align(&self) -> Result<TimeAlignment> {
+        if self.devices.is_empty() || self.channel.direction() != BladerfDirection::RX || !self.config.format.has_metadata() {
+            return Err(BladerfError::Inval.into());
+        }
+
+        let mut triggers = Vec::with_capacity(self.devices.len());
+
+        let capture = self.fire_and_capture(&mut triggers).await;
+        let disarmed = self.disarm(&triggers).await;
+
+        let capture = capture?;
+        disarmed?;
+
+        TimeAlignment::from_captures(&[capture])
+    }
+
+    async fn fire_and_capture(&self, triggers: &mut Vec<Trigger>) -> Result<Vec<u64>> {
+        self.fire(triggers).await?;
+
+        let mut capture = Vec::with_capacity(self.devices.len());
+
+        for dev in self.devices {
+            let mut rx = RxStream::new(dev, self.config)?;
+            let mut samples = vec![0; self.config.values_per_sample()];
+            let mut meta = Metadata { flags: META_FLAG_RX_NOW, ..Default::default() };
+
+            rx.read(&mut samples, &mut meta).await?;
+            capture.push(meta.timestamp);
+        }
+
+        Ok(capture)
+    }
+
+    /*
+     * Arm every board, recording each trigger before arming so a partial failure
+     * can be undone, then start RX and fire the master
+     */
+    async fn fire(&self, triggers: &mut Vec<Trigger>) -> Result<()> {
+        for (i, dev) in self.devices.iter().enumerate() {
+            let mut trigger = dev.trigger_init(self.channel, self.signal)?;
+            trigger.role = if i == 0 { TriggerRole::Master } else { TriggerRole::Slave };
+
+            triggers.push(trigger);
+            dev.trigger_arm(&trigger, true).await?;
+        }
+
+        for dev in self.devices {
+            dev.enable_rx().await?;
+        }
+
+        self.devices[0].trigger_fire(&triggers[0]).await?;
+
+        let deadline = Instant::now() + TRIGGER_TIMEOUT;
+
+        for (dev, trigger) in self.devices.iter().zip(triggers.iter()) {
+            while !dev.trigger_state(trigger).await?.has_fired {
+                if Instant::now() >= deadline {
+                    return Err(BladerfError::Timeout.into());
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /* Try every board even if one fails, and report the first failure */
+    async fn disarm(&self, triggers: &[Trigger]) -> Result<()> {
+        let mut result = Ok(());
+
+        for (dev, trigger) in self.devices.iter().zip(triggers) {
+            let disarmed = dev.trigger_arm(trigger, false).await;
+
+            if result.is_ok() {
+                result = disarmed;
+            }
+        }
+
+        result
+    }
+}
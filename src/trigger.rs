@@ -0,0 +1,179 @@
+use crate::error::BladerfError;
+use crate::nios::nios_access::{nios_read_trigger, nios_write_trigger};
+use crate::{BladerfBoard, BladerfChannel, Device};
+use anyhow::Result;
+use core::future::Future;
+
+/* Trigger control register (NIOS 8x8 targets 3 and 4) bits */
+pub const BLADERF_TRIGGER_REG_ARM: u8 = 1 << 0;
+pub const BLADERF_TRIGGER_REG_FIRE: u8 = 1 << 1;
+pub const BLADERF_TRIGGER_REG_MASTER: u8 = 1 << 2;
+/* Read-only level of the trigger line */
+pub const BLADERF_TRIGGER_REG_LINE: u8 = 1 << 3;
+
+/*
+ * Signal the trigger is carried on. The first three name the same FPGA trigger
+ * line as it is routed on each board: J71 pin 4 on the bladeRF 1, J51 pin 1 on
+ * the bladeRF 2.0 and mini expansion pin 1 on the xA4. The user signals are
+ * reserved for custom FPGA logic; the stock NIOS firmware refuses them.
+ */
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum TriggerSignal {
+    J71_4,
+    J51_1,
+    MiniExp1,
+    User0,
+    User1,
+    User2,
+    User3,
+    User4,
+    User5,
+    User6,
+    User7,
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Default)]
+pub enum TriggerRole {
+    #[default]
+    Disabled,
+    /* Drives the trigger line when fired */
+    Master,
+    /* Waits for the master to drive the trigger line */
+    Slave,
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub struct Trigger {
+    pub channel: BladerfChannel,
+    pub role: TriggerRole,
+    pub signal: TriggerSignal,
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Default)]
+pub struct TriggerState {
+    pub is_armed: bool,
+    pub has_fired: bool,
+    pub fire_requested: bool,
+}
+
+impl From<u8> for TriggerState {
+    fn from(reg: u8) -> Self {
+        TriggerState {
+            is_armed: reg & BLADERF_TRIGGER_REG_ARM != 0,
+            has_fired: reg & BLADERF_TRIGGER_REG_LINE != 0,
+            fire_requested: reg & BLADERF_TRIGGER_REG_FIRE != 0,
+        }
+    }
+}
+
+/* Control register value arming (or disarming) a trigger in `role`, given its current value */
+pub fn trigger_arm_reg(reg: u8, role: TriggerRole, arm: bool) -> Result<u8> {
+    if !arm {
+        return Ok(reg & !(BLADERF_TRIGGER_REG_ARM | BLADERF_TRIGGER_REG_FIRE));
+    }
+
+    let reg = match role {
+        TriggerRole::Master => reg | BLADERF_TRIGGER_REG_MASTER,
+        TriggerRole::Slave => reg & !BLADERF_TRIGGER_REG_MASTER,
+        TriggerRole::Disabled => return Err(BladerfError::Inval.into()),
+    };
+
+    Ok(reg | BLADERF_TRIGGER_REG_ARM)
+}
+
+/*
+ * A trigger lives on the first channel of a direction, and the external signals
+ * only exist on the board they are routed on. The xA4 cannot be told apart from
+ * other bladeRF 2.0 boards by product ID, so MiniExp1 is accepted on all of them.
+ */
+pub fn check_trigger(board: BladerfBoard, channel: BladerfChannel, signal: TriggerSignal) -> Result<()> {
+    let routed = match signal {
+        TriggerSignal::J71_4 => board == BladerfBoard::BladeRF1,
+        TriggerSignal::J51_1 | TriggerSignal::MiniExp1 => board == BladerfBoard::BladeRF2,
+        _ => true,
+    };
+
+    if channel.index() != 0 || !routed {
+        return Err(BladerfError::Inval.into());
+    }
+
+    Ok(())
+}
+
+/* Trigger operations as used by the timebase coordinator, implemented by Device */
+pub trait TriggerTransport {
+    fn trigger_init(&self, channel: BladerfChannel, signal: TriggerSignal) -> Result<Trigger>;
+
+    fn trigger_arm(&self, trigger: &Trigger, arm: bool) -> impl Future<Output = Result<()>>;
+
+    fn trigger_fire(&self, trigger: &Trigger) -> impl Future<Output = Result<()>>;
+
+    fn trigger_state(&self, trigger: &Trigger) -> impl Future<Output = Result<TriggerState>>;
+}
+
+#[cfg(feature = "nusb")]
+impl TriggerTransport for Device {
+    fn trigger_init(&self, channel: BladerfChannel, signal: TriggerSignal) -> Result<Trigger> {
+        Device::trigger_init(self, channel, signal)
+    }
+
+    async fn trigger_arm(&self, trigger: &Trigger, arm: bool) -> Result<()> {
+        Device::trigger_arm(self, trigger, arm).await
+    }
+
+    async fn trigger_fire(&self, trigger: &Trigger) -> Result<()> {
+        Device::trigger_fire(self, trigger).await
+    }
+
+    async fn trigger_state(&self, trigger: &Trigger) -> Result<TriggerState> {
+        Device::trigger_state(self, trigger).await
+    }
+}
+
+/*
+ * Usage mirrors libbladerf: init a trigger on every board, make one of them the
+ * master and the rest slaves, arm them all, start the streams and fire the master.
+ * Sample flow on each armed channel is held back until the trigger line goes active.
+ */
+#[cfg(feature = "nusb")]
+impl Device {
+    pub fn trigger_init(&self, channel: BladerfChannel, signal: TriggerSignal) -> Result<Trigger> {
+        check_trigger(self.board()?, channel, signal)?;
+
+        Ok(Trigger {
+            channel,
+            role: TriggerRole::Disabled,
+            signal,
+        })
+    }
+
+    pub async fn trigger_arm(&self, trigger: &Trigger, arm: bool) -> Result<()> {
+        let direction = trigger.channel.direction();
+        let reg = nios_read_trigger(self, direction, trigger.signal).await?;
+        let reg = trigger_arm_reg(reg, trigger.role, arm)?;
+
+        nios_write_trigger(self, direction, trigger.signal, reg).await?;
+
+        Ok(())
+    }
+
+    /* Only the master drives the line; firing it releases every armed board */
+    pub async fn trigger_fire(&self, trigger: &Trigger) -> Result<()> {
+        if trigger.role != TriggerRole::Master {
+            return Err(BladerfError::Inval.into());
+        }
+
+        let direction = trigger.channel.direction();
+        let reg = nios_read_trigger(self, direction, trigger.signal).await?;
+
+        nios_write_trigger(self, direction, trigger.signal, reg | BLADERF_TRIGGER_REG_FIRE).await?;
+
+        Ok(())
+    }
+
+    pub async fn trigger_state(&self, trigger: &Trigger) -> Result<TriggerState> {
+        let reg = nios_read_trigger(self, trigger.channel.direction(), trigger.signal).await?;
+
+        Ok(TriggerState::from(reg))
+    }
+}
@@ -1,5 +1,7 @@
 use crate::usb::nusb::nusb_host_to_bladerf;
 use crate::Device;
+#[cfg(feature = "nusb")]
+use alloc::vec::Vec;
 use anyhow::Result;
 
 #[cfg(feature = "nusb")]
@@ -33,6 +35,15 @@ pub async fn bulk_transfer_in<const endpoint: u8, const len: usize>(device: &Dev
     }
 }
 
+#[cfg(feature = "nusb")]
+pub async fn bulk_transfer_in_vec<const endpoint: u8>(device: &Device, len: usize) -> Result<Vec<u8>> {
+    if let Some(int) = &device.interface {
+        nusb::nusb_bulk_transfer_in_vec::<endpoint>(int, len).await
+    } else {
+        Err(anyhow::anyhow!("Device not connected"))
+    }
+}
+
 #[cfg(feature = "nusb")]
 pub async fn bulk_transfer_out<const endpoint: u8>(device: &Device, buf: &[u8]) -> Result<()> {
     if let Some(int) = &device.interface {
@@ -1,3 +1,5 @@
+#[cfg(feature = "std")]
+use crate::lms6::path::RF_PATH_AUTO;
 use crate::usb::Device;
 use alloc::vec::Vec;
 use nusb::transfer::{ControlIn, ControlOut, ControlType, Recipient, RequestBuffer, ResponseBuffer};
@@ -45,6 +47,14 @@ pub async fn nusb_bulk_transfer_in<const endpoint: u8, const len: usize>(interfa
     Ok(out.data)
 }
 
+pub async fn nusb_bulk_transfer_in_vec<const endpoint: u8>(interface: &Interface, len: usize) -> anyhow::Result<Vec<u8>> {
+    let buff = RequestBuffer::new(len);
+    let out = interface.bulk_in(endpoint, buff).await;
+    out.status?;
+
+    Ok(out.data)
+}
+
 pub async fn nusb_bulk_transfer_out<const endpoint: u8>(interface: &Interface, buf: &[u8]) -> anyhow::Result<ResponseBuffer> {
     let resp = interface.bulk_out(endpoint, buf.to_vec()).await;
     resp.status?;
@@ -68,6 +78,18 @@ pub async fn list_devices<const len: usize, const vid: u16>() -> anyhow::Result<
                     product_id: device.product_id(),
                     interface: None,
                     device,
+                    #[cfg(feature = "std")]
+                    nios_lock: futures::lock::Mutex::new(()),
+                    #[cfg(feature = "std")]
+                    config_lock: futures::lock::Mutex::new(()),
+                    #[cfg(feature = "std")]
+                    quick_tune_profiles: Default::default(),
+                    #[cfg(feature = "std")]
+                    oversample_eight_bit: Default::default(),
+                    #[cfg(feature = "std")]
+                    retune_queues: Default::default(),
+                    #[cfg(feature = "std")]
+                    rf_path_manual: [const { core::sync::atomic::AtomicU8::new(RF_PATH_AUTO) }; 2],
                 });
             count += 1;
         }
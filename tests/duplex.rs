@@ -0,0 +1,74 @@
+use libbladerf_native_rs::stream::metadata::METADATA_HEADER_SIZE;
+use libbladerf_native_rs::stream::*;
+use libbladerf_native_rs::BladerfDirection;
+use std::cell::{Cell, RefCell};
+use std::collections::VecDeque;
+
+/* Stand-in transport that loops every TX message straight back to RX */
+struct LoopbackTransport {
+    timestamp: Cell<u64>,
+    queue: RefCell<VecDeque<Vec<u8>>>,
+}
+
+impl SampleTransport for LoopbackTransport {
+    async fn read_message(&self, _len: usize) -> anyhow::Result<Vec<u8>> {
+        loop {
+            if let Some(msg) = self.queue.borrow_mut().pop_front() {
+                return Ok(msg);
+            }
+
+            tokio::task::yield_now().await;
+        }
+    }
+
+    async fn write_message(&self, buf: &[u8]) -> anyhow::Result<()> {
+        self.queue.borrow_mut().push_back(buf.to_vec());
+        Ok(())
+    }
+
+    async fn get_timestamp(&self, _dir: BladerfDirection) -> anyhow::Result<u64> {
+        Ok(self.timestamp.get())
+    }
+}
+
+#[tokio::test]
+async fn full_duplex_loopback() {
+    let transport = LoopbackTransport {
+        timestamp: Cell::new(0),
+        queue: RefCell::new(VecDeque::new()),
+    };
+
+    let config = StreamConfig { message_size: METADATA_HEADER_SIZE + 32, ..Default::default() };
+    let (mut rx, mut tx) = duplex(&transport, config).unwrap();
+
+    let burst: Vec<i16> = (1..=24).collect();
+
+    let transmit = async {
+        let mut meta = Metadata {
+            timestamp: 1000,
+            flags: META_FLAG_TX_BURST_START | META_FLAG_TX_BURST_END,
+            ..Default::default()
+        };
+
+        tx.write(&burst, &mut meta).await.unwrap();
+    };
+
+    let receive = async {
+        let mut samples = vec![0; 24];
+        let mut meta = Metadata { timestamp: 1000, ..Default::default() };
+
+        rx.read(&mut samples, &mut meta).await.unwrap();
+
+        (samples, meta)
+    };
+
+    let ((), (samples, meta)) = tokio::join!(transmit, receive);
+
+    assert_eq!(meta.timestamp, 1000);
+    assert_eq!(meta.actual_count, 12);
+    assert_eq!(samples, burst);
+
+    /* Both handles share one timebase */
+    assert_eq!(rx.timestamp(), 1012);
+    assert_eq!(tx.timestamp(), 1016);
+}
@@ -0,0 +1,35 @@
+use libbladerf_native_rs::nios::fpga_config::*;
+
+#[test]
+fn fpga_config_round_trips_and_keeps_unknown_bits() {
+    let reg = BLADERF_GPIO_LMS_RX_ENABLE | (2 << BLADERF_GPIO_RX_MUX_SHIFT) | BLADERF_GPIO_TIMESTAMP | (1 << 30);
+    let mut config = FpgaConfig::from(reg);
+
+    assert!(config.lms_rx_enable);
+    assert_eq!(config.rx_mux().unwrap(), RxMux::Counter32Bit);
+    assert!(config.timestamps);
+    assert!(!config.agc);
+    assert_eq!(config.other, 1 << 30);
+
+    config.agc = true;
+    config.set_rx_mux(RxMux::DigitalLoopback);
+
+    assert_eq!(
+        u32::from(config),
+        BLADERF_GPIO_LMS_RX_ENABLE | (4 << BLADERF_GPIO_RX_MUX_SHIFT) | BLADERF_GPIO_TIMESTAMP | BLADERF_GPIO_AGC_ENABLE | (1 << 30)
+    );
+}
+
+#[test]
+fn fpga_config_keeps_unknown_rx_mux_values() {
+    use libbladerf_native_rs::error::BladerfError;
+
+    let reg = 6 << BLADERF_GPIO_RX_MUX_SHIFT;
+    let mut config = FpgaConfig::from(reg);
+
+    let err = config.rx_mux().unwrap_err();
+    assert_eq!(err.downcast_ref::<BladerfError>(), Some(&BladerfError::Unexpected));
+
+    config.timestamps = true;
+    assert_eq!(u32::from(config), reg | BLADERF_GPIO_TIMESTAMP);
+}
@@ -0,0 +1,146 @@
+use libbladerf_native_rs::lms6::frequency::*;
+
+#[test]
+fn tuning_round_trips_across_range() {
+    /* PLL resolution is LMS_REFERENCE_HZ / 2^23 / x, about 4.6 Hz at worst */
+    for hz in (300_000_000..=3_800_000_000u64).step_by(997_331) {
+        let freq = LmsFreq::from_frequency(hz).unwrap();
+
+        assert!(freq.frequency().abs_diff(hz) <= 3, "{hz} Hz tuned to {}", freq.frequency());
+        assert!(freq.nfrac < 1 << 23);
+        assert!(freq.vcocap <= VCOCAP_MAX_VALUE);
+        assert_eq!(freq.low_band, hz < BLADERF1_BAND_HIGH);
+    }
+}
+
+#[test]
+fn tuning_matches_band_table() {
+    let freq = LmsFreq::from_frequency(300_000_000).unwrap();
+    assert_eq!((freq.freqsel, freq.nint, freq.nfrac), (0x2f, 125, 0));
+
+    let freq = LmsFreq::from_frequency(915_000_000).unwrap();
+    assert_eq!((freq.freqsel, freq.nint), (0x3e, 190));
+
+    let freq = LmsFreq::from_frequency(2_400_000_000).unwrap();
+    assert_eq!((freq.freqsel, freq.nint, freq.nfrac), (0x2c, 125, 0));
+
+    assert_eq!(LmsFreq::from_frequency(BLADERF1_FREQUENCY_MAX).unwrap().freqsel, 0x3c);
+
+    /* The VCOCAP estimate rises across a band, from 15 to just under 55 */
+    assert_eq!(LmsFreq::from_frequency(285_625_001).unwrap().vcocap, 15);
+    assert_eq!(LmsFreq::from_frequency(336_875_000 - 1).unwrap().vcocap, 54);
+    assert!(LmsFreq::from_frequency(100_000_000).is_err());
+    assert!(LmsFreq::from_frequency(BLADERF1_FREQUENCY_MAX + 1).is_err());
+}
+
+/* VTUNE of a PLL whose stable VCOCAP window is `low..=high` */
+fn vtune(vcocap: u8, low: u8, high: u8) -> u8 {
+    if vcocap < low {
+        VCO_HIGH
+    } else if vcocap > high {
+        VCO_LOW
+    } else {
+        VCO_NORM
+    }
+}
+
+#[tokio::test]
+async fn vcocap_search_centers_on_window() {
+    for start in [0, 20, 31, VCOCAP_MAX_VALUE] {
+        let vcocap = search_vcocap(start, |v| async move { Ok(vtune(v, 24, 36)) }).await.unwrap();
+        assert_eq!(vcocap, 30);
+    }
+
+    let vcocap = search_vcocap(5, |v| async move { Ok(vtune(v, 0, 3)) }).await.unwrap();
+    assert_eq!(vcocap, 1);
+
+    /* No window at all: the PLL never locks */
+    assert!(search_vcocap(10, |_| async { Ok(VCO_HIGH) }).await.is_err());
+    assert!(search_vcocap(10, |_| async { Ok(VCO_HIGH | VCO_LOW) }).await.is_err());
+}
+
+#[test]
+fn gain_stages_split_like_libbladerf() {
+    use libbladerf_native_rs::lms6::gain::*;
+
+    assert_eq!(rx_gain_stages(5), (LnaGain::Bypass, 5, 0));
+    assert_eq!(rx_gain_stages(8), (LnaGain::Mid, 5, 0));
+    assert_eq!(rx_gain_stages(40), (LnaGain::Max, 30, 3));
+    assert_eq!(rx_gain_stages(100), (LnaGain::Max, 30, 30));
+
+    assert_eq!(tx_gain_stages(10), (-35, 10));
+    assert_eq!(tx_gain_stages(40), (-20, 25));
+    assert_eq!(tx_gain_stages(BLADERF_TX_GAIN_MAX), (BLADERF_TXVGA1_GAIN_MAX, 25));
+
+    for db in BLADERF_RXVGA1_GAIN_MIN..=BLADERF_RXVGA1_GAIN_MAX {
+        assert_eq!(rxvga1_db(rxvga1_code(db)), db);
+    }
+    assert_eq!(rxvga1_db(0), BLADERF_RXVGA1_GAIN_MIN);
+}
+
+#[test]
+fn bandwidth_selects_narrowest_covering_filter() {
+    use libbladerf_native_rs::lms6::lpf::*;
+
+    assert_eq!(LMS_BANDWIDTHS[lms_bandwidth_code(1_000_000) as usize], 1_500_000);
+    assert_eq!(LMS_BANDWIDTHS[lms_bandwidth_code(1_500_000) as usize], 1_500_000);
+    assert_eq!(LMS_BANDWIDTHS[lms_bandwidth_code(3_841_000) as usize], 5_000_000);
+    assert_eq!(LMS_BANDWIDTHS[lms_bandwidth_code(20_000_000) as usize], 20_000_000);
+    assert_eq!(lms_bandwidth_code(40_000_000), 0);
+}
+
+#[test]
+fn dc_calibration_module_table() {
+    use libbladerf_native_rs::lms6::calibrate::DcCalModule;
+
+    let table = [
+        (DcCalModule::LpfTuning, 0x00, 1 << 5, 1, None, false),
+        (DcCalModule::TxLpf, 0x30, 1 << 1, 2, Some((0x3f, 0x80)), false),
+        (DcCalModule::RxLpf, 0x50, 1 << 3, 2, Some((0x5f, 0x80)), true),
+        (DcCalModule::RxVga2, 0x60, 1 << 4, 5, Some((0x6e, 0xc0)), true),
+    ];
+
+    for (module, base, clock, submodules, comparators, is_rx) in table {
+        assert_eq!(module.base(), base, "{module:?}");
+        assert_eq!(module.clock(), clock, "{module:?}");
+        assert_eq!(module.submodules(), submodules, "{module:?}");
+        assert_eq!(module.comparators(), comparators, "{module:?}");
+        assert_eq!(module.is_rx(), is_rx, "{module:?}");
+    }
+}
+
+#[test]
+fn loopback_decodes_registers() {
+    use libbladerf_native_rs::lms6::loopback::{decode_loopback, Loopback};
+
+    assert_eq!(decode_loopback(0x20, 0x04), Loopback::BbTxLpfRxVga2);
+    assert_eq!(decode_loopback(0x20, 0x08), Loopback::BbTxVga1RxVga2);
+    assert_eq!(decode_loopback(0x40, 0x04), Loopback::BbTxLpfRxLpf);
+    assert_eq!(decode_loopback(0x40, 0x08), Loopback::BbTxVga1RxLpf);
+    assert_eq!(decode_loopback(0x02, 0x00), Loopback::RfLna2);
+    assert_eq!(decode_loopback(0x00, 0x00), Loopback::None);
+
+    /* LBEN_OPIN loops back to the output pins, which is not an RX loopback */
+    assert_eq!(decode_loopback(0x10, 0x04), Loopback::None);
+    /* A baseband switch without a TX source selected carries nothing */
+    assert_eq!(decode_loopback(0x20, 0x00), Loopback::None);
+}
+
+#[test]
+fn rf_path_decodes_registers() {
+    use libbladerf_native_rs::lms6::path::{decode_pa, Lna, Pa};
+
+    assert_eq!(Lna::from(0), Lna::None);
+    assert_eq!(Lna::from(2), Lna::Lna2);
+    /* Only the two low bits select the LNA */
+    assert_eq!(Lna::from(0x37), Lna::Lna3);
+    assert_eq!(Pa::from(1), Pa::Pa1);
+    assert_eq!(Pa::from(0x07), Pa::Aux);
+
+    assert_eq!(decode_pa(0x02), Pa::None);
+    assert_eq!(decode_pa(0x0a), Pa::Pa1);
+    assert_eq!(decode_pa(0x12), Pa::Pa2);
+    assert_eq!(decode_pa(0x00), Pa::Aux);
+    /* PA1 and PA2 enable bits take precedence over the AUX PA power down */
+    assert_eq!(decode_pa(0x08), Pa::Pa1);
+}
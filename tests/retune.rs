@@ -0,0 +1,219 @@
+use libbladerf_native_rs::error::BladerfError;
+use libbladerf_native_rs::nios::nios_access::retune_failure;
+use libbladerf_native_rs::retune::hopping::{HopOutcome, HopScheduler};
+use libbladerf_native_rs::retune::queue::RetuneQueue;
+use libbladerf_native_rs::retune::{QuickTune, RetuneResponse, RetuneTransport, RETUNE_QUEUE_DEPTH};
+use libbladerf_native_rs::BladerfDirection;
+use std::cell::{Cell, RefCell};
+
+#[derive(Default)]
+struct MockRetuner {
+    timestamp: Cell<u64>,
+    /* Device time when requests reach the FPGA, if later than `timestamp` */
+    arrival: Cell<u64>,
+    /* A scheduled retune the FPGA refuses */
+    refuse: Cell<Option<u64>>,
+    /* Timestamps of retunes accepted into the queue */
+    queue: RefCell<Vec<u64>>,
+}
+
+impl RetuneTransport for MockRetuner {
+    async fn retune(&self, _dir: BladerfDirection, timestamp: u64, _tune: &QuickTune) -> anyhow::Result<RetuneResponse> {
+        let now = self.timestamp.get().max(self.arrival.get());
+
+        if timestamp <= now {
+            return Ok(RetuneResponse { duration: 5, tuned: true });
+        }
+
+        let mut queue = self.queue.borrow_mut();
+        queue.retain(|&ts| ts > now);
+
+        if queue.len() == RETUNE_QUEUE_DEPTH || self.refuse.get() == Some(timestamp) {
+            return Err(BladerfError::QueueFull.into());
+        }
+
+        queue.push(timestamp);
+        Ok(RetuneResponse::default())
+    }
+
+    async fn get_timestamp(&self, _dir: BladerfDirection) -> anyhow::Result<u64> {
+        Ok(self.timestamp.get())
+    }
+
+    async fn pending_retunes(&self, _dir: BladerfDirection) -> anyhow::Result<usize> {
+        let now = self.timestamp.get();
+        let mut queue = self.queue.borrow_mut();
+        queue.retain(|&ts| ts > now);
+
+        Ok(queue.len())
+    }
+}
+
+fn tune(frequency: u64) -> anyhow::Result<QuickTune> {
+    Ok(QuickTune::BladeRF2 {
+        nios_profile: (frequency / 1_000_000) as u16,
+        rffe_profile: 0,
+        port: 0,
+        spdt: 0,
+    })
+}
+
+#[tokio::test]
+async fn hop_scheduler_keeps_queue_within_depth() {
+    let retuner = MockRetuner::default();
+    let frequencies = [915_000_000, 920_000_000, 925_000_000];
+    let mut computed = 0;
+
+    let mut scheduler = HopScheduler::new(&retuner, BladerfDirection::RX, &frequencies, 1000, 10_000, |f| {
+        computed += 1;
+        tune(f)
+    })
+    .unwrap();
+    assert_eq!(computed, 3);
+
+    let reports = scheduler.pump().await.unwrap();
+    assert_eq!(reports.len(), RETUNE_QUEUE_DEPTH);
+    assert!(reports.iter().all(|r| r.outcome == HopOutcome::Scheduled));
+    assert_eq!(reports[4].frequency, 920_000_000);
+    assert_eq!(reports[4].timestamp, 14_000);
+
+    /* Nothing has played out yet, so there is no room for more */
+    assert!(scheduler.pump().await.unwrap().is_empty());
+
+    /* Two hops have started */
+    retuner.timestamp.set(11_000);
+    let reports = scheduler.pump().await.unwrap();
+    assert_eq!(reports.len(), 2);
+    assert_eq!(reports[0].index, RETUNE_QUEUE_DEPTH as u64);
+}
+
+#[tokio::test]
+async fn hop_scheduler_reports_late_and_missed_hops() {
+    let retuner = MockRetuner::default();
+    retuner.timestamp.set(12_500);
+
+    let mut scheduler = HopScheduler::new(&retuner, BladerfDirection::TX, &[2_400_000_000], 1000, 10_000, tune).unwrap();
+
+    let reports = scheduler.pump().await.unwrap();
+
+    assert_eq!(reports[0].outcome, HopOutcome::Missed { count: 2 });
+    assert_eq!((reports[0].index, reports[0].timestamp), (0, 10_000));
+    assert_eq!(reports[1].outcome, HopOutcome::Late { duration: 5 });
+    assert_eq!(reports[2].outcome, HopOutcome::Scheduled);
+}
+
+#[tokio::test]
+async fn hop_scheduler_reports_late_and_failed_scheduled_hops() {
+    let retuner = MockRetuner::default();
+    retuner.timestamp.set(9_000);
+    /* The first hop's start passes while its request is on the way */
+    retuner.arrival.set(10_500);
+    retuner.refuse.set(Some(12_000));
+
+    let mut scheduler = HopScheduler::new(&retuner, BladerfDirection::RX, &[915_000_000], 1000, 10_000, tune).unwrap();
+
+    let reports = scheduler.pump().await.unwrap();
+
+    assert_eq!(reports[0].outcome, HopOutcome::Late { duration: 5 });
+    assert_eq!(reports[1].outcome, HopOutcome::Scheduled);
+    assert_eq!(reports[2].outcome, HopOutcome::Failed);
+    assert_eq!(reports[2].timestamp, 12_000);
+
+    /* Neither of those takes a queue slot */
+    assert_eq!(reports.len(), RETUNE_QUEUE_DEPTH + 2);
+}
+
+#[tokio::test]
+async fn hop_scheduler_skips_a_long_gap_at_once() {
+    let retuner = MockRetuner::default();
+    /* An hour of 1 ms hops at 30.72 MHz */
+    retuner.timestamp.set(3600 * 30_720_000 + 5);
+
+    let mut scheduler = HopScheduler::new(&retuner, BladerfDirection::RX, &[915_000_000, 920_000_000], 30_720, 0, tune).unwrap();
+
+    let reports = scheduler.pump().await.unwrap();
+
+    assert_eq!(reports[0].outcome, HopOutcome::Missed { count: 3_600_000 });
+    assert_eq!(reports[1].outcome, HopOutcome::Late { duration: 5 });
+    assert_eq!(reports[1].index, 3_600_000);
+    assert_eq!(reports.len(), 2 + RETUNE_QUEUE_DEPTH);
+    assert_eq!(scheduler.next_index(), 3_600_000 + 1 + RETUNE_QUEUE_DEPTH as u64);
+}
+
+#[test]
+fn retune_failure_depends_on_timestamp() {
+    /* Timestamp 0 asks for an immediate retune */
+    let now = retune_failure(0);
+    let scheduled = retune_failure(1000);
+
+    assert_eq!(now.downcast_ref::<BladerfError>(), Some(&BladerfError::Unexpected));
+    assert_eq!(scheduled.downcast_ref::<BladerfError>(), Some(&BladerfError::QueueFull));
+}
+
+#[test]
+fn retune_queue_tracks_pending_retunes() {
+    let mut queue = RetuneQueue::default();
+
+    for i in 0..RETUNE_QUEUE_DEPTH as u64 {
+        queue.push(1000 + i * 100);
+    }
+    assert!(queue.is_full());
+
+    /* Retunes at 1000 and 1100 are due by then */
+    queue.expire(1150);
+    assert_eq!(queue.pending(), RETUNE_QUEUE_DEPTH - 2);
+    assert!(!queue.is_full());
+
+    queue.clear();
+    assert_eq!(queue.pending(), 0);
+}
+
+#[test]
+fn quick_tune_from_lms_tuning() {
+    use libbladerf_native_rs::lms6::frequency::LmsFreq;
+
+    let freq = LmsFreq::from_frequency(915_000_000).unwrap();
+
+    match QuickTune::from(freq) {
+        QuickTune::BladeRF1 { nint, nfrac, freqsel, low_band, .. } => {
+            assert_eq!((nint, nfrac, freqsel, low_band), (freq.nint, freq.nfrac, freq.freqsel, true));
+        }
+        QuickTune::BladeRF2 { .. } => panic!("expected bladeRF 1 settings"),
+    }
+}
+
+#[test]
+fn quick_tune_captures_xb200_path() {
+    use libbladerf_native_rs::retune::quick_tune::*;
+
+    /* RX filter switch 2 and bypass path, TX filter switch 1 with the TX side disabled */
+    let gpio = BLADERF_XB_RX_ENABLE | (2 << BLADERF_XB_RX_SHIFT) | 0x20 | (1 << BLADERF_XB_TX_SHIFT);
+
+    let rx = xb200_quick_tune_gpio(gpio, BladerfDirection::RX);
+    assert_eq!(rx, LMS_FREQ_XB_200_ENABLE | LMS_FREQ_XB_200_MODULE_RX | (2 << 4) | (2 << 2));
+    assert_eq!(xb200_quick_tune_gpio(gpio, BladerfDirection::TX), 0);
+
+    let tx = xb200_quick_tune_gpio(BLADERF_XB_TX_ENABLE | (3 << BLADERF_XB_TX_SHIFT) | 0x04, BladerfDirection::TX);
+    assert_eq!(tx, LMS_FREQ_XB_200_ENABLE | (3 << 4) | (1 << 2));
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn quick_tune_serde_round_trip() {
+    let table = vec![
+        tune(915_000_000).unwrap(),
+        QuickTune::BladeRF1 {
+            nint: 190,
+            nfrac: 5_242_880,
+            freqsel: 0x3e,
+            vcocap: 33,
+            low_band: true,
+            xb_gpio: 0,
+        },
+    ];
+
+    let json = serde_json::to_string(&table).unwrap();
+    let decoded: Vec<QuickTune> = serde_json::from_str(&json).unwrap();
+
+    assert_eq!(decoded, table);
+}
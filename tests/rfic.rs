@@ -0,0 +1,26 @@
+use libbladerf_native_rs::error::BladerfError;
+use libbladerf_native_rs::rfic::oversample::check_oversample;
+use libbladerf_native_rs::rfic::BladerfGainMode;
+use libbladerf_native_rs::stream::SampleFormat;
+use libbladerf_native_rs::BladerfVersion;
+
+const FPGA_V0_15: BladerfVersion = BladerfVersion { major: 0, minor: 15, patch: 0 };
+const MANUAL: [BladerfGainMode; 2] = [BladerfGainMode::Manual; 2];
+
+fn error_of(result: anyhow::Result<()>) -> Option<BladerfError> {
+    result.err().and_then(|e| e.downcast_ref::<BladerfError>().copied())
+}
+
+#[test]
+fn oversample_refuses_unsupported_setups() {
+    assert!(check_oversample(SampleFormat::Sc8Q7Meta, FPGA_V0_15, &MANUAL).is_ok());
+    assert!(check_oversample(SampleFormat::Sc8Q7, FPGA_V0_15, &MANUAL).is_ok());
+
+    assert_eq!(error_of(check_oversample(SampleFormat::Sc16Q11Meta, FPGA_V0_15, &MANUAL)), Some(BladerfError::Inval));
+
+    let old_fpga = BladerfVersion { major: 0, minor: 14, patch: 9 };
+    assert_eq!(error_of(check_oversample(SampleFormat::Sc8Q7Meta, old_fpga, &MANUAL)), Some(BladerfError::Unsupported));
+
+    let agc = [BladerfGainMode::Manual, BladerfGainMode::SlowAttackAgc];
+    assert_eq!(error_of(check_oversample(SampleFormat::Sc8Q7Meta, FPGA_V0_15, &agc)), Some(BladerfError::Inval));
+}
@@ -0,0 +1,413 @@
+use libbladerf_native_rs::error::BladerfError;
+use libbladerf_native_rs::stream::metadata::{pack_metadata, unpack_metadata, METADATA_HEADER_SIZE};
+use libbladerf_native_rs::stream::rx::RxStream;
+use libbladerf_native_rs::stream::tx::TxStream;
+use libbladerf_native_rs::stream::*;
+use libbladerf_native_rs::BladerfDirection;
+use std::cell::{Cell, RefCell};
+use std::collections::VecDeque;
+
+struct MockTransport {
+    timestamp: Cell<u64>,
+    written: RefCell<Vec<Vec<u8>>>,
+    received: RefCell<VecDeque<Vec<u8>>>,
+}
+
+impl MockTransport {
+    fn new(timestamp: u64) -> Self {
+        MockTransport {
+            timestamp: Cell::new(timestamp),
+            written: RefCell::new(Vec::new()),
+            received: RefCell::new(VecDeque::new()),
+        }
+    }
+
+    /* Queue an RX message whose samples count up from the header timestamp */
+    fn push_rx(&self, timestamp: u64, samples: usize) {
+        let mut msg = pack_metadata(timestamp, 0).to_vec();
+
+        for i in 0..samples as u64 {
+            let value = (timestamp + i) as i16;
+            msg.extend_from_slice(&value.to_le_bytes());
+            msg.extend_from_slice(&(-value).to_le_bytes());
+        }
+
+        self.received.borrow_mut().push_back(msg);
+    }
+}
+
+impl SampleTransport for MockTransport {
+    async fn read_message(&self, _len: usize) -> anyhow::Result<Vec<u8>> {
+        self.received.borrow_mut().pop_front().ok_or(anyhow::anyhow!("No RX data"))
+    }
+
+    async fn write_message(&self, buf: &[u8]) -> anyhow::Result<()> {
+        self.written.borrow_mut().push(buf.to_vec());
+        Ok(())
+    }
+
+    async fn get_timestamp(&self, _dir: BladerfDirection) -> anyhow::Result<u64> {
+        Ok(self.timestamp.get())
+    }
+}
+
+fn small_config() -> StreamConfig {
+    /* 4 samples per message */
+    StreamConfig { message_size: METADATA_HEADER_SIZE + 16, ..Default::default() }
+}
+
+fn sample_at(msg: &[u8], idx: usize) -> (i16, i16) {
+    let off = METADATA_HEADER_SIZE + idx * 4;
+    (
+        i16::from_le_bytes([msg[off], msg[off + 1]]),
+        i16::from_le_bytes([msg[off + 2], msg[off + 3]]),
+    )
+}
+
+#[tokio::test]
+async fn tx_burst_is_scheduled_and_zero_padded() {
+    let transport = MockTransport::new(100);
+    let mut tx = TxStream::new(&transport, small_config()).unwrap();
+
+    let samples = [1, -1, 2, -2, 3, -3, 4, -4, 5, -5, 6, -6];
+    let mut meta = Metadata {
+        timestamp: 1000,
+        flags: META_FLAG_TX_BURST_START | META_FLAG_TX_BURST_END,
+        ..Default::default()
+    };
+
+    tx.write(&samples, &mut meta).await.unwrap();
+
+    let written = transport.written.borrow();
+    assert_eq!(written.len(), 2);
+
+    assert_eq!(unpack_metadata(&written[0]).0, 1000);
+    assert_eq!(sample_at(&written[0], 3), (4, -4));
+
+    assert_eq!(unpack_metadata(&written[1]).0, 1004);
+    assert_eq!(sample_at(&written[1], 1), (6, -6));
+    assert_eq!(sample_at(&written[1], 2), (0, 0));
+    assert_eq!(sample_at(&written[1], 3), (0, 0));
+
+    assert!(!tx.in_burst());
+    assert_eq!(tx.timestamp(), 1008);
+}
+
+#[tokio::test]
+async fn tx_burst_in_the_past_is_rejected() {
+    let transport = MockTransport::new(5000);
+    let mut tx = TxStream::new(&transport, small_config()).unwrap();
+
+    let mut meta = Metadata {
+        timestamp: 4000,
+        flags: META_FLAG_TX_BURST_START,
+        ..Default::default()
+    };
+
+    let err = tx.write(&[0, 0], &mut meta).await.unwrap_err();
+
+    assert_eq!(err.downcast_ref::<BladerfError>(), Some(&BladerfError::TimePast));
+    assert!(transport.written.borrow().is_empty());
+}
+
+#[tokio::test]
+async fn rx_read_starts_at_requested_timestamp() {
+    let transport = MockTransport::new(0);
+    transport.push_rx(100, 4);
+    transport.push_rx(104, 4);
+    transport.push_rx(108, 4);
+
+    let mut rx = RxStream::new(&transport, small_config()).unwrap();
+    let mut samples = [0; 10];
+    let mut meta = Metadata { timestamp: 106, ..Default::default() };
+
+    rx.read(&mut samples, &mut meta).await.unwrap();
+
+    assert_eq!(meta.timestamp, 106);
+    assert_eq!(meta.actual_count, 5);
+    assert_eq!(meta.status, 0);
+    assert_eq!(samples, [106, -106, 107, -107, 108, -108, 109, -109, 110, -110]);
+    assert_eq!(rx.timestamp(), 111);
+}
+
+#[tokio::test]
+async fn rx_read_stops_at_discontinuity() {
+    let transport = MockTransport::new(0);
+    transport.push_rx(100, 4);
+    transport.push_rx(200, 4);
+
+    let mut rx = RxStream::new(&transport, small_config()).unwrap();
+    let mut samples = [0; 12];
+    let mut meta = Metadata { flags: META_FLAG_RX_NOW, ..Default::default() };
+
+    rx.read(&mut samples, &mut meta).await.unwrap();
+
+    assert_eq!(meta.timestamp, 100);
+    assert_eq!(meta.actual_count, 4);
+    assert_eq!(meta.status & META_STATUS_OVERRUN, META_STATUS_OVERRUN);
+
+    /* The samples after the gap are returned by the next read */
+    let mut meta = Metadata { flags: META_FLAG_RX_NOW, ..Default::default() };
+    rx.read(&mut samples[..4], &mut meta).await.unwrap();
+
+    assert_eq!(meta.timestamp, 200);
+    assert_eq!(meta.actual_count, 2);
+    assert_eq!(samples[..4], [200, -200, 201, -201]);
+}
+
+#[tokio::test]
+async fn rx_read_of_passed_timestamp_is_rejected() {
+    let transport = MockTransport::new(0);
+    transport.push_rx(100, 4);
+
+    let mut rx = RxStream::new(&transport, small_config()).unwrap();
+    let mut samples = [0; 2];
+    let mut meta = Metadata { timestamp: 50, ..Default::default() };
+
+    let err = rx.read(&mut samples, &mut meta).await.unwrap_err();
+
+    assert_eq!(err.downcast_ref::<BladerfError>(), Some(&BladerfError::TimePast));
+}
+
+#[tokio::test]
+async fn rx_short_read_is_an_io_error() {
+    let transport = MockTransport::new(0);
+    transport.push_rx(100, 3);
+
+    let mut rx = RxStream::new(&transport, small_config()).unwrap();
+    let mut samples = [0; 2];
+    let mut meta = Metadata { flags: META_FLAG_RX_NOW, ..Default::default() };
+
+    let err = rx.read(&mut samples, &mut meta).await.unwrap_err();
+
+    assert_eq!(err.downcast_ref::<BladerfError>(), Some(&BladerfError::Io));
+}
+
+#[tokio::test]
+async fn rx_stats_count_overruns_and_dropped_samples() {
+    let transport = MockTransport::new(0);
+    transport.push_rx(100, 4);
+    transport.push_rx(110, 4);
+    transport.push_rx(114, 4);
+
+    let mut rx = RxStream::new(&transport, small_config()).unwrap();
+    let mut samples = [0; 24];
+    let mut meta = Metadata { flags: META_FLAG_RX_NOW, ..Default::default() };
+
+    rx.read(&mut samples, &mut meta).await.unwrap();
+    assert_eq!(meta.actual_count, 4);
+
+    rx.read(&mut samples[..16], &mut meta).await.unwrap();
+    assert_eq!(meta.actual_count, 8);
+
+    let stats = rx.stats();
+    assert_eq!(stats.buffers, 3);
+    assert_eq!(stats.samples, 12);
+    assert_eq!(stats.overruns, 1);
+    assert_eq!(stats.dropped_samples, 6);
+}
+
+#[tokio::test]
+async fn tx_stats_count_late_messages_as_underruns() {
+    let transport = MockTransport::new(0);
+    let mut tx = TxStream::new(&transport, small_config()).unwrap();
+
+    let mut meta = Metadata { timestamp: 10, flags: META_FLAG_TX_BURST_START, ..Default::default() };
+    tx.write(&[1; 8], &mut meta).await.unwrap();
+    assert_eq!(meta.status, 0);
+
+    /* The hardware overtakes the burst in the middle of it */
+    transport.timestamp.set(20);
+
+    let mut meta = Metadata::default();
+    tx.write(&[1; 8], &mut meta).await.unwrap();
+    assert_eq!(meta.status, META_STATUS_UNDERRUN);
+
+    let mut meta = Metadata { flags: META_FLAG_TX_BURST_END, ..Default::default() };
+    tx.write(&[1; 4], &mut meta).await.unwrap();
+    assert_eq!(meta.status, META_STATUS_UNDERRUN);
+
+    let stats = tx.stats();
+    assert_eq!(stats.buffers, 3);
+    assert_eq!(stats.underruns, 2);
+}
+
+#[tokio::test]
+async fn rx_stream_and_tx_sink_adapters() {
+    use futures::{SinkExt, StreamExt};
+    use libbladerf_native_rs::stream::adapter::SampleBuffer;
+
+    let transport = MockTransport::new(0);
+    transport.push_rx(100, 4);
+    transport.push_rx(104, 4);
+
+    let rx = RxStream::new(&transport, small_config()).unwrap().into_stream(3);
+    let buffers: Vec<_> = rx.collect().await;
+
+    /* Two full buffers, then the mock runs dry and the stream ends on the error */
+    assert_eq!(buffers.len(), 3);
+    assert_eq!(buffers[0].as_ref().unwrap().meta.timestamp, 100);
+    assert_eq!(buffers[1].as_ref().unwrap().meta.timestamp, 103);
+    assert_eq!(buffers[1].as_ref().unwrap().samples, [103, -103, 104, -104, 105, -105]);
+    assert!(buffers[2].is_err());
+
+    let mut tx = Box::pin(TxStream::new(&transport, small_config()).unwrap().into_sink());
+    tx.send(SampleBuffer {
+        samples: vec![7; 4],
+        meta: Metadata {
+            timestamp: 50,
+            flags: META_FLAG_TX_BURST_START | META_FLAG_TX_BURST_END,
+            ..Default::default()
+        },
+    })
+    .await
+    .unwrap();
+
+    assert_eq!(transport.written.borrow().len(), 1);
+}
+
+#[tokio::test]
+async fn mimo_stream_counts_sample_times() {
+    use libbladerf_native_rs::stream::channel::deinterleave;
+
+    let transport = MockTransport::new(0);
+    let config = StreamConfig { channels: 2, ..small_config() };
+    assert_eq!(config.samples_per_message(), 2);
+
+    /* push_rx writes 4 I/Q pairs, i.e. 2 sample times of 2 channels */
+    transport.push_rx(100, 4);
+    transport.push_rx(102, 4);
+
+    let mut rx = RxStream::new(&transport, config).unwrap();
+    let mut meta = Metadata { timestamp: 101, ..Default::default() };
+    let channels = rx.read_channels(2, &mut meta).await.unwrap();
+
+    assert_eq!(meta.actual_count, 2);
+    assert_eq!(channels[0], [102, -102, 102, -102]);
+    assert_eq!(channels[1], [103, -103, 103, -103]);
+    assert_eq!(deinterleave(&[1, 2, 3, 4, 5, 6, 7, 8], 2).unwrap(), [vec![1, 2, 5, 6], vec![3, 4, 7, 8]]);
+
+    let mut tx = TxStream::new(&transport, config).unwrap();
+    let mut meta = Metadata { timestamp: 10, flags: META_FLAG_TX_BURST_START | META_FLAG_TX_BURST_END, ..Default::default() };
+    tx.write_channels(&[&[1, 1, 2, 2], &[-1, -1, -2, -2]], &mut meta).await.unwrap();
+
+    let written = transport.written.borrow();
+    assert_eq!(sample_at(&written[0], 0), (1, 1));
+    assert_eq!(sample_at(&written[0], 1), (-1, -1));
+    assert_eq!(sample_at(&written[0], 3), (-2, -2));
+    assert_eq!(unpack_metadata(&written[1]).0, 12);
+}
+
+#[test]
+fn stream_rejects_invalid_channel_counts() {
+    use libbladerf_native_rs::stream::channel::deinterleave;
+
+    let transport = MockTransport::new(0);
+
+    for channels in [0, 3] {
+        let config = StreamConfig { channels, ..small_config() };
+        let err = RxStream::new(&transport, config).err().unwrap();
+
+        assert_eq!(err.downcast_ref::<BladerfError>(), Some(&BladerfError::Inval));
+        assert!(TxStream::new(&transport, config).is_err());
+        assert!(duplex(&transport, config).is_err());
+    }
+
+    assert!(deinterleave(&[1, 2, 3, 4], 0).is_err());
+}
+
+#[test]
+fn stream_rejects_invalid_message_sizes() {
+    let transport = MockTransport::new(0);
+
+    /* No room for samples after the header, then a partial sample time */
+    for message_size in [METADATA_HEADER_SIZE, METADATA_HEADER_SIZE + 6] {
+        let config = StreamConfig { message_size, ..small_config() };
+        let err = RxStream::new(&transport, config).err().unwrap();
+
+        assert_eq!(err.downcast_ref::<BladerfError>(), Some(&BladerfError::Inval));
+        assert!(TxStream::new(&transport, config).is_err());
+    }
+}
+
+#[tokio::test]
+async fn sc8q7_stream_packs_samples_into_bytes() {
+    let transport = MockTransport::new(0);
+    let config = StreamConfig { format: SampleFormat::Sc8Q7Meta, ..small_config() };
+    assert_eq!(config.samples_per_message(), 8);
+
+    let mut tx = TxStream::new(&transport, config).unwrap();
+    let mut meta = Metadata { timestamp: 10, flags: META_FLAG_TX_BURST_START | META_FLAG_TX_BURST_END, ..Default::default() };
+
+    let err = tx.write(&[1, 1], &mut meta).await.unwrap_err();
+    assert_eq!(err.downcast_ref::<BladerfError>(), Some(&BladerfError::Inval));
+
+    tx.write_sc8(&[1, -1, 2, -2], &mut meta).await.unwrap();
+
+    let msg = transport.written.borrow()[0].clone();
+    assert_eq!(msg.len(), METADATA_HEADER_SIZE + 16);
+    assert_eq!(msg[METADATA_HEADER_SIZE..METADATA_HEADER_SIZE + 5], [1, 0xff, 2, 0xfe, 0]);
+
+    transport.received.borrow_mut().push_back(msg);
+
+    let mut rx = RxStream::new(&transport, config).unwrap();
+    let mut samples = [0i8; 4];
+    let mut meta = Metadata { timestamp: 10, ..Default::default() };
+    rx.read_sc8(&mut samples, &mut meta).await.unwrap();
+
+    assert_eq!(samples, [1, -1, 2, -2]);
+}
+
+#[tokio::test]
+async fn packet_stream_frames_payload() {
+    use libbladerf_native_rs::stream::packet::{Packet, PacketStream};
+
+    let transport = MockTransport::new(0);
+    let config = StreamConfig { format: SampleFormat::PacketMeta, ..small_config() };
+    let mut stream = PacketStream::new(&transport, config).unwrap();
+
+    let packet = Packet { core: 3, flags: 0x10, timestamp: 77, payload: vec![0xdeadbeef, 1] };
+    stream.send(&packet).await.unwrap();
+
+    let too_long = Packet { payload: vec![0; 5], ..packet.clone() };
+    assert!(stream.send(&too_long).await.is_err());
+
+    let msg = transport.written.borrow()[0].clone();
+    assert_eq!(msg[..4], [2, 0, 3, 0]);
+    assert_eq!(msg[METADATA_HEADER_SIZE..METADATA_HEADER_SIZE + 4], [0xef, 0xbe, 0xad, 0xde]);
+
+    transport.received.borrow_mut().push_back(msg);
+    assert_eq!(stream.recv().await.unwrap(), packet);
+
+    /* A length running past the end of the message */
+    let mut msg = transport.written.borrow()[0].clone();
+    msg[0] = 5;
+    transport.received.borrow_mut().push_back(msg);
+
+    let err = stream.recv().await.unwrap_err();
+    assert_eq!(err.downcast_ref::<BladerfError>(), Some(&BladerfError::Unexpected));
+
+    for config in [small_config(), StreamConfig { message_size: METADATA_HEADER_SIZE, ..config }] {
+        let err = PacketStream::new(&transport, config).err().unwrap();
+        assert_eq!(err.downcast_ref::<BladerfError>(), Some(&BladerfError::Inval));
+    }
+}
+
+#[test]
+fn counter_checker_measures_drops() {
+    use libbladerf_native_rs::nios::fpga_config::RxMux;
+    use libbladerf_native_rs::stream::counter::CounterChecker;
+
+    assert!(CounterChecker::new(RxMux::Baseband).is_err());
+
+    let mut checker = CounterChecker::new(RxMux::Counter32Bit).unwrap();
+
+    /* 0xfffe, 0xffff, 0x10000, then 0x10003 after two lost samples */
+    assert_eq!(checker.check(&[-2, 0, -1, 0, 0, 1]), 0);
+    assert_eq!(checker.check(&[3, 1]), 1);
+    assert_eq!(checker.dropped, 2);
+    assert_eq!(checker.drop_rate(), 2.0 / 6.0);
+
+    let mut checker = CounterChecker::new(RxMux::Counter12Bit).unwrap();
+    assert_eq!(checker.check(&[2046, 0, 2047, 0, -2048, 0, -2047, 0]), 0);
+}
@@ -0,0 +1,118 @@
+use libbladerf_native_rs::error::BladerfError;
+use libbladerf_native_rs::stream::metadata::pack_metadata;
+use libbladerf_native_rs::stream::{SampleTransport, StreamConfig};
+use libbladerf_native_rs::timebase::{TimeAlignment, TimebaseCoordinator, TimebaseTransport};
+use libbladerf_native_rs::trigger::{Trigger, TriggerRole, TriggerSignal, TriggerState, TriggerTransport};
+use libbladerf_native_rs::{BladerfChannel, BladerfDirection};
+use std::cell::Cell;
+
+#[test]
+fn alignment_takes_median_offset() {
+    let captures = vec![vec![1000, 1500, 900], vec![2000, 2503, 1900], vec![3000, 3499, 2901]];
+
+    let alignment = TimeAlignment::from_captures(&captures).unwrap();
+
+    assert_eq!(alignment.offsets, vec![0, 500, -100]);
+    assert_eq!(alignment.spread, 4);
+    assert_eq!(alignment.to_device(1, 10_000), 10_500);
+    assert_eq!(alignment.to_common(2, 9_900), 10_000);
+
+    assert!(TimeAlignment::from_captures(&[vec![1, 2], vec![3]]).is_err());
+}
+
+/* A board whose first RX message after the trigger starts at `edge`, failing at one step if asked to */
+struct MockBoard {
+    edge: u64,
+    fail_arm: bool,
+    fail_fire: bool,
+    armed: Cell<bool>,
+    fired: Cell<bool>,
+}
+
+impl MockBoard {
+    fn new(edge: u64) -> Self {
+        MockBoard { edge, fail_arm: false, fail_fire: false, armed: Cell::new(false), fired: Cell::new(false) }
+    }
+}
+
+impl TriggerTransport for MockBoard {
+    fn trigger_init(&self, channel: BladerfChannel, signal: TriggerSignal) -> anyhow::Result<Trigger> {
+        Ok(Trigger { channel, role: TriggerRole::Disabled, signal })
+    }
+
+    async fn trigger_arm(&self, _trigger: &Trigger, arm: bool) -> anyhow::Result<()> {
+        if arm && self.fail_arm {
+            return Err(BladerfError::Unexpected.into());
+        }
+
+        self.armed.set(arm);
+        Ok(())
+    }
+
+    async fn trigger_fire(&self, _trigger: &Trigger) -> anyhow::Result<()> {
+        if self.fail_fire {
+            return Err(BladerfError::Unexpected.into());
+        }
+
+        self.fired.set(true);
+        Ok(())
+    }
+
+    async fn trigger_state(&self, _trigger: &Trigger) -> anyhow::Result<TriggerState> {
+        Ok(TriggerState { is_armed: self.armed.get(), has_fired: true, fire_requested: self.fired.get() })
+    }
+}
+
+impl SampleTransport for MockBoard {
+    async fn read_message(&self, len: usize) -> anyhow::Result<Vec<u8>> {
+        let mut msg = pack_metadata(self.edge, 0).to_vec();
+        msg.resize(len, 0);
+        Ok(msg)
+    }
+
+    async fn write_message(&self, _buf: &[u8]) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    async fn get_timestamp(&self, _dir: BladerfDirection) -> anyhow::Result<u64> {
+        Ok(self.edge)
+    }
+}
+
+impl TimebaseTransport for MockBoard {
+    async fn enable_rx(&self) -> anyhow::Result<()> {
+        Ok(())
+    }
+}
+
+#[tokio::test]
+async fn coordinator_measures_gated_rx_timestamps() {
+    let boards = [MockBoard::new(1000), MockBoard::new(1250), MockBoard::new(700)];
+    let coordinator = TimebaseCoordinator::new(&boards, BladerfChannel::RX1, TriggerSignal::J51_1, StreamConfig::default());
+
+    let alignment = coordinator.align().await.unwrap();
+
+    assert_eq!(alignment.offsets, vec![0, 250, -300]);
+    assert!(boards.iter().all(|board| !board.armed.get()));
+}
+
+#[tokio::test]
+async fn coordinator_disarms_every_board_on_failure() {
+    let config = StreamConfig::default();
+
+    /* The third board cannot be armed */
+    let mut boards = [MockBoard::new(0), MockBoard::new(0), MockBoard::new(0)];
+    boards[2].fail_arm = true;
+
+    let coordinator = TimebaseCoordinator::new(&boards, BladerfChannel::RX1, TriggerSignal::J51_1, config);
+    assert!(coordinator.align().await.is_err());
+    assert!(boards.iter().all(|board| !board.armed.get()));
+
+    /* Everything is armed, but the master does not fire */
+    let mut boards = [MockBoard::new(0), MockBoard::new(0)];
+    boards[0].fail_fire = true;
+
+    let coordinator = TimebaseCoordinator::new(&boards, BladerfChannel::RX1, TriggerSignal::J51_1, config);
+    assert!(coordinator.align().await.is_err());
+    assert!(boards.iter().all(|board| !board.armed.get()));
+}
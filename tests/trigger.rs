@@ -0,0 +1,70 @@
+use libbladerf_native_rs::error::BladerfError;
+use libbladerf_native_rs::trigger::*;
+
+#[test]
+fn trigger_state_decodes_register() {
+    let state = TriggerState::from(BLADERF_TRIGGER_REG_ARM | BLADERF_TRIGGER_REG_LINE);
+
+    assert!(state.is_armed);
+    assert!(state.has_fired);
+    assert!(!state.fire_requested);
+
+    assert_eq!(TriggerState::from(BLADERF_TRIGGER_REG_FIRE | BLADERF_TRIGGER_REG_MASTER), TriggerState {
+        fire_requested: true,
+        ..Default::default()
+    });
+}
+
+#[test]
+fn trigger_arm_sets_role_bits() {
+    let master = trigger_arm_reg(0, TriggerRole::Master, true).unwrap();
+    assert_eq!(master, BLADERF_TRIGGER_REG_ARM | BLADERF_TRIGGER_REG_MASTER);
+
+    /* Arming as a slave drops a master bit left from an earlier run */
+    let slave = trigger_arm_reg(BLADERF_TRIGGER_REG_MASTER, TriggerRole::Slave, true).unwrap();
+    assert_eq!(slave, BLADERF_TRIGGER_REG_ARM);
+
+    /* Disarming clears arm and fire but keeps the role */
+    let disarmed = trigger_arm_reg(master | BLADERF_TRIGGER_REG_FIRE, TriggerRole::Master, false).unwrap();
+    assert_eq!(disarmed, BLADERF_TRIGGER_REG_MASTER);
+
+    let err = trigger_arm_reg(0, TriggerRole::Disabled, true).unwrap_err();
+    assert_eq!(err.downcast_ref::<BladerfError>(), Some(&BladerfError::Inval));
+}
+
+#[test]
+fn trigger_requests_address_the_direction_target() {
+    use libbladerf_native_rs::nios::nios_access::pack_trigger_request;
+    use libbladerf_native_rs::BladerfDirection;
+
+    for signal in [TriggerSignal::J71_4, TriggerSignal::J51_1, TriggerSignal::MiniExp1] {
+        let read = pack_trigger_request(BladerfDirection::RX, signal, false, 0).unwrap();
+        assert_eq!(read[..6], [b'A', 4, 0, 0, 0, 0]);
+
+        let write = pack_trigger_request(BladerfDirection::TX, signal, true, BLADERF_TRIGGER_REG_ARM).unwrap();
+        assert_eq!(write[..6], [b'A', 3, 1, 0, 0, BLADERF_TRIGGER_REG_ARM]);
+    }
+
+    let err = pack_trigger_request(BladerfDirection::RX, TriggerSignal::User0, false, 0).unwrap_err();
+    assert_eq!(err.downcast_ref::<BladerfError>(), Some(&BladerfError::Inval));
+}
+
+#[test]
+fn trigger_signal_must_be_routed_on_the_board() {
+    use libbladerf_native_rs::{BladerfBoard, BladerfChannel};
+
+    assert!(check_trigger(BladerfBoard::BladeRF1, BladerfChannel::RX1, TriggerSignal::J71_4).is_ok());
+    assert!(check_trigger(BladerfBoard::BladeRF2, BladerfChannel::TX1, TriggerSignal::J51_1).is_ok());
+    assert!(check_trigger(BladerfBoard::BladeRF2, BladerfChannel::RX1, TriggerSignal::MiniExp1).is_ok());
+    assert!(check_trigger(BladerfBoard::BladeRF1, BladerfChannel::RX1, TriggerSignal::User3).is_ok());
+
+    for (board, channel, signal) in [
+        (BladerfBoard::BladeRF2, BladerfChannel::RX1, TriggerSignal::J71_4),
+        (BladerfBoard::BladeRF1, BladerfChannel::RX1, TriggerSignal::J51_1),
+        (BladerfBoard::BladeRF1, BladerfChannel::TX1, TriggerSignal::MiniExp1),
+        (BladerfBoard::BladeRF2, BladerfChannel::RX2, TriggerSignal::J51_1),
+    ] {
+        let err = check_trigger(board, channel, signal).unwrap_err();
+        assert_eq!(err.downcast_ref::<BladerfError>(), Some(&BladerfError::Inval));
+    }
+}